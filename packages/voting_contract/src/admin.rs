@@ -6,7 +6,8 @@ use cosmwasm_std::{
 use crate::msg::HandleMsg;
 
 use crate::state::{
-    get_active_proposals, read_config, set_active_proposals, set_config, Proposal, VoteTotals,
+    get_active_proposals, read_config, read_pending_owner, set_active_proposals, set_config,
+    set_pending_owner, Proposal, VoteTotals,
 };
 
 use crate::voting::tally;
@@ -86,11 +87,17 @@ pub fn admin_commands<S: Storage, A: Api, Q: Querier>(
 
         HandleMsg::Tally { proposal } => tally(deps, env, proposal),
 
-        HandleMsg::ChangeOwner { new_owner } => {
-            config.admin = new_owner;
+        HandleMsg::ProposeOwner { new_owner } => {
+            set_pending_owner(&mut deps.storage, &new_owner);
 
-            set_config(&mut deps.storage, &config);
-            Ok(HandleResponse::default())
+            Ok(HandleResponse {
+                messages: vec![],
+                log: vec![log(
+                    "pending_owner",
+                    new_owner.map(|o| o.0).unwrap_or_default(),
+                )],
+                data: None,
+            })
         }
         HandleMsg::CreateSnapshot { proposal } => create_snapshot(deps, env, proposal),
         HandleMsg::InitVote {
@@ -102,6 +109,34 @@ pub fn admin_commands<S: Storage, A: Api, Q: Querier>(
     }
 }
 
+/// The second half of an ownership transfer: the proposed admin takes over
+pub fn accept_owner<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> StdResult<HandleResponse> {
+    if read_pending_owner(&deps.storage).as_ref() != Some(&env.message.sender) {
+        return Err(StdError::generic_err(
+            "Only the proposed owner can accept the ownership",
+        ));
+    }
+
+    let mut config = read_config(&deps.storage)?;
+    let previous = config.admin.clone();
+    config.admin = env.message.sender.clone();
+    set_config(&mut deps.storage, &config);
+    set_pending_owner(&mut deps.storage, &None);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "accept_owner"),
+            log("previous_owner", previous.as_str()),
+            log("owner", env.message.sender.as_str()),
+        ],
+        data: None,
+    })
+}
+
 pub fn init_vote<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
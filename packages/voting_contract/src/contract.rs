@@ -3,7 +3,7 @@ use cosmwasm_std::{
     StdResult, Storage, Uint128,
 };
 
-use crate::admin::{admin_commands, SNAPSHOTS};
+use crate::admin::{accept_owner, admin_commands, SNAPSHOTS};
 use crate::msg::{HandleMsg, InitMsg, QueryAnswer, QueryMsg};
 use crate::state::{get_active_proposals, get_inactive_proposals, set_config, Config, VoteTotals};
 use crate::voting::{
@@ -53,6 +53,7 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
         HandleMsg::Vote { proposal, vote } => try_vote(deps, env, proposal, vote),
         HandleMsg::SetPassword { password } => set_password(deps, env, password),
         HandleMsg::NotifyBalanceChange { changes } => change_votes(deps, env, changes),
+        HandleMsg::AcceptOwner {} => accept_owner(deps, env),
         _ => admin_commands(deps, env, msg),
     }
 }
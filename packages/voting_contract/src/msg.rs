@@ -39,9 +39,10 @@ pub enum HandleMsg {
     Tally {
         proposal: u64,
     },
-    ChangeOwner {
-        new_owner: HumanAddr,
+    ProposeOwner {
+        new_owner: Option<HumanAddr>,
     },
+    AcceptOwner {},
     NotifyBalanceChange {
         changes: Vec<VoteChange>,
     },
@@ -10,6 +10,7 @@ pub const PREFIX_CONFIG: &[u8] = b"config";
 pub static CONFIG_KEY: &[u8] = b"config";
 pub static ACTIVE_PROPOSALS_KEY: &[u8] = b"active_proposals";
 pub static INACTIVE_PROPOSALS_KEY: &[u8] = b"inactive_proposals";
+pub static PENDING_OWNER_KEY: &[u8] = b"pending_owner";
 
 pub static PROPOSALS: &[u8] = b"proposals";
 
@@ -32,6 +33,21 @@ pub fn read_config<S: Storage>(store: &S) -> StdResult<Config> {
     Ok(consts)
 }
 
+/// the address the admin proposed to hand the contract over to, until it accepts
+pub fn set_pending_owner<S: Storage>(storage: &mut S, owner: &Option<HumanAddr>) {
+    let owner_bytes: Vec<u8> = bincode2::serialize(&owner).unwrap();
+
+    let mut config_store = PrefixedStorage::new(PREFIX_CONFIG, storage);
+    config_store.set(PENDING_OWNER_KEY, &owner_bytes);
+}
+
+pub fn read_pending_owner<S: Storage>(store: &S) -> Option<HumanAddr> {
+    let config_store = ReadonlyPrefixedStorage::new(PREFIX_CONFIG, store);
+    let owner_bytes = config_store.get(PENDING_OWNER_KEY).unwrap_or_default();
+
+    bincode2::deserialize(&owner_bytes).unwrap_or_default()
+}
+
 #[derive(Serialize, Debug, Deserialize, Clone, PartialEq)]
 pub struct Config {
     pub admin: HumanAddr,
@@ -1,35 +1,184 @@
 use cosmwasm_std::{
-    debug_print, log, to_binary, Api, BankMsg, Coin, CosmosMsg, Env, Extern, HandleResponse,
-    HumanAddr, Querier, StdError, StdResult, Storage, WasmMsg,
+    log, to_binary, Api, BankMsg, Coin, CosmosMsg, Decimal, Env, Extern, HandleResponse, HumanAddr,
+    Querier, StdError, StdResult, Storage, WasmMsg,
 };
 
 use crate::claim::claim_multiple;
 use secretstaking_token::msg::HandleMsg as SecretStakingHandleMsg;
 
-use crate::state::store_frozen_exchange_rate;
-
-use crate::staking::{exchange_rate, redelegate_msg};
-use crate::types::config::{read_config, set_config};
-use crate::types::killswitch::KillSwitch;
-use crate::types::validator_set::{get_validator_set, set_validator_set, DEFAULT_WEIGHT};
+use crate::constants::{FEE_RESOLUTION, MAX_DEV_FEE};
+
+use crate::pause::{ensure_not_paused, pause_logs};
+use crate::staking::redelegate_msg;
+use crate::types::compounding::{read_compounding, set_compounding, MAX_COMPOUND_BOUNTY};
+use crate::types::config::{read_config, set_config, Config};
+use crate::types::insurance::{read_insurance_fund, set_insurance_fund};
+use crate::types::pause::{read_pause_flags, set_pause_flags};
+use crate::types::rate_guard::{read_rate_guard, set_rate_guard};
+use crate::types::rate_subscribers::{
+    read_rate_subscribers, set_rate_subscribers, MAX_RATE_SUBSCRIBERS,
+};
+use crate::types::roles::{has_role, read_roles, set_roles, Role};
+use crate::types::scoring_config::{set_scoring_config, ScoringConfig};
+use crate::types::timelock::{read_pending_owner, read_timelock, set_pending_owner, set_timelock};
+use crate::types::validator_performance::checkpoint_performance;
+use crate::types::validator_policy::{read_validator_policy, set_validator_policy};
+use crate::types::validator_set::{get_validator_set, set_validator_set};
+use crate::validators::{migration_step, redistribute_stake};
+use crate::wind_down::{open_withdraws, start_wind_down};
 
 use crate::msg::HandleMsg;
 use cargo_common::tokens::TokenHandleMessage;
 
+/// The role that may run an admin command besides the owner. None means owner only
+fn required_role(msg: &HandleMsg) -> Option<Role> {
+    match msg {
+        HandleMsg::AddValidator { .. }
+        | HandleMsg::RemoveValidator { .. }
+        | HandleMsg::Redelegate { .. }
+        | HandleMsg::ReplaceValidatorSet { .. }
+        | HandleMsg::ChangeWeight { .. }
+        | HandleMsg::ChangeValidatorPolicy { .. }
+        | HandleMsg::SetScoringConfig { .. } => Some(Role::ValidatorManager),
+        // where the fees go is the owner's call
+        HandleMsg::ChangeDevFee {
+            dev_address: None, ..
+        }
+        | HandleMsg::SetCompoundBounty { .. }
+        | HandleMsg::SetInsuranceFeeShare { .. } => Some(Role::FeeManager),
+        HandleMsg::ClaimMaturedWithdraws { .. } => Some(Role::Keeper),
+        HandleMsg::CancelAction { .. } => Some(Role::Guardian),
+        _ => None,
+    }
+}
+
+/// Commands that can move funds or lock users in. They have to be queued with ProposeAction, and
+/// only run once the timelock delay passed
+fn is_timelocked(msg: &HandleMsg) -> bool {
+    matches!(
+        msg,
+        HandleMsg::RecoverScrt { .. }
+            | HandleMsg::RecoverToken { .. }
+            | HandleMsg::ChangeUnbondingTime { .. }
+            | HandleMsg::SetVotingContract { .. }
+            | HandleMsg::SetMintingGov { .. }
+            | HandleMsg::AddRateSubscriber { .. }
+            | HandleMsg::SetTimelockDelay { .. }
+            | HandleMsg::ChangeDevFee {
+                dev_address: Some(_),
+                ..
+            }
+    )
+}
+
 /// This file contains only permissioned functions
-/// Can only be run by contract deployer or the contract itself
+/// Can only be run by the owner (contract deployer), the contract itself, or a role the owner
+/// granted - see required_role
 pub fn admin_commands<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     msg: HandleMsg,
 ) -> StdResult<HandleResponse> {
-    let mut config = read_config(&deps.storage)?;
+    let config = read_config(&deps.storage)?;
 
     // authenticate admin
     if config.admin != env.message.sender && env.contract.address != env.message.sender {
-        return Err(StdError::generic_err(
-            "Admin commands can only be ran from deployer address",
-        ));
+        let allowed = match required_role(&msg) {
+            Some(role) => has_role(&deps.storage, &env.message.sender, role)?,
+            None => false,
+        };
+
+        if !allowed {
+            return Err(StdError::generic_err(
+                "Admin commands can only be ran from deployer address, or by a role allowed to",
+            ));
+        }
+    }
+
+    match msg {
+        HandleMsg::ProposeAction { msg } => {
+            if !is_timelocked(&msg) {
+                return Err(StdError::generic_err(
+                    "Only timelocked commands can be proposed - run this one directly",
+                ));
+            }
+
+            let mut timelock = read_timelock(&deps.storage)?;
+            let action = timelock.propose(*msg, env.message.sender.clone(), env.block.time)?;
+            let (id, eta) = (action.id, action.eta);
+            set_timelock(&mut deps.storage, &timelock)?;
+
+            Ok(HandleResponse {
+                messages: vec![],
+                log: vec![
+                    log("action", "propose_action"),
+                    log("id", id),
+                    log("eta", eta),
+                ],
+                data: None,
+            })
+        }
+
+        HandleMsg::ExecuteAction { id } => {
+            let mut timelock = read_timelock(&deps.storage)?;
+            let action = timelock.take(id)?;
+            if action.eta > env.block.time {
+                return Err(StdError::generic_err(format!(
+                    "Action {} can't be executed before {}",
+                    id, action.eta
+                )));
+            }
+            set_timelock(&mut deps.storage, &timelock)?;
+
+            let mut res = run_admin_command(deps, env, config, action.msg)?;
+            res.log.insert(0, log("executed_action", id));
+            Ok(res)
+        }
+
+        HandleMsg::CancelAction { id } => {
+            let mut timelock = read_timelock(&deps.storage)?;
+            timelock.take(id)?;
+            set_timelock(&mut deps.storage, &timelock)?;
+
+            Ok(HandleResponse {
+                messages: vec![],
+                log: vec![
+                    log("action", "cancel_action"),
+                    log("account", env.message.sender.as_str()),
+                    log("id", id),
+                ],
+                data: None,
+            })
+        }
+
+        msg if is_timelocked(&msg) => Err(StdError::generic_err(
+            "This command is timelocked - queue it with propose_action",
+        )),
+
+        msg => run_admin_command(deps, env, config, msg),
+    }
+}
+
+/// runs an admin command that was already authenticated
+fn run_admin_command<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    mut config: Config,
+    msg: HandleMsg,
+) -> StdResult<HandleResponse> {
+    let validator_op = matches!(
+        msg,
+        HandleMsg::AddValidator { .. }
+            | HandleMsg::RemoveValidator { .. }
+            | HandleMsg::Redelegate { .. }
+            | HandleMsg::ReplaceValidatorSet { .. }
+            | HandleMsg::ChangeWeight { .. }
+            | HandleMsg::ChangeValidatorPolicy { .. }
+    );
+    if validator_op {
+        ensure_not_paused(&deps.storage, "Validator operations", |paused| {
+            paused.validator_ops
+        })?;
     }
 
     match msg {
@@ -53,6 +202,12 @@ pub fn admin_commands<S: Storage, A: Api, Q: Querier>(
             dev_address,
         } => {
             if let Some(dev_fee) = dev_fee {
+                if dev_fee > MAX_DEV_FEE {
+                    return Err(StdError::generic_err(format!(
+                        "Dev fee cannot be more than {}",
+                        MAX_DEV_FEE
+                    )));
+                }
                 config.dev_fee = dev_fee;
             }
 
@@ -127,14 +282,16 @@ pub fn admin_commands<S: Storage, A: Api, Q: Querier>(
             let removed = validator_set.remove(&address, redelegate_flag)?;
 
             if let Some(validator) = removed {
-                let to_stake = validator.staked;
-                let dest_validator = validator_set.stake(to_stake)?;
-
-                if redelegate_flag {
-                    messages.push(redelegate_msg(&address, &dest_validator, to_stake));
-                }
-                validator_set.rebalance();
+                messages.extend(redistribute_stake(
+                    &mut deps.storage,
+                    &mut validator_set,
+                    &validator,
+                    validator.staked,
+                    env.block.time,
+                    config.unbonding_time,
+                )?);
             }
+            checkpoint_performance(&mut deps.storage, &validator_set, env.block.time)?;
             set_validator_set(&mut deps.storage, &validator_set)?;
 
             Ok(HandleResponse {
@@ -165,56 +322,155 @@ pub fn admin_commands<S: Storage, A: Api, Q: Querier>(
         }
 
         HandleMsg::Redelegate { from, to } => {
+            if from == to {
+                return Err(StdError::generic_err(
+                    "Cannot redelegate to the same validator",
+                ));
+            }
+
             let mut validator_set = get_validator_set(&deps.storage)?;
-            let mut messages: Vec<CosmosMsg> = vec![];
-            let mut weight: u8 = DEFAULT_WEIGHT;
-            let removed = validator_set.remove(&from, true)?;
+            let source = validator_set.get(&from).ok_or_else(|| {
+                StdError::generic_err(format!("Validator {} is not in the validator set", from))
+            })?;
+            if source.is_redelegation_blocked(env.block.time) {
+                return Err(StdError::generic_err(format!(
+                    "Cannot redelegate from {} - it received a redelegation that matures at {}",
+                    from, source.redelegation_cooldown
+                )));
+            }
 
-            if let Some(validator) = removed {
-                let to_stake = validator.staked;
-                weight = validator.weight;
-                validator_set.stake_at(&to, to_stake)?;
+            match validator_set.get(&to) {
+                Some(target) if target.is_active() => {}
+                _ => {
+                    return Err(StdError::generic_err(format!(
+                        "Cannot redelegate to {} - it is not an active validator of the set",
+                        to
+                    )))
+                }
+            }
 
-                messages.push(redelegate_msg(&from, &to, to_stake));
+            let policy = read_validator_policy(&deps.storage)?;
+            let max_commission = Decimal::from_ratio(policy.max_commission as u128, FEE_RESOLUTION);
+            match deps
+                .querier
+                .query_validators()?
+                .iter()
+                .find(|v| v.address.0 == to)
+            {
+                Some(validator) if validator.commission <= max_commission => {}
+                Some(_) => {
+                    return Err(StdError::generic_err(format!(
+                        "Cannot redelegate to {} - its commission is above the maximum",
+                        to
+                    )))
+                }
+                None => {
+                    return Err(StdError::generic_err(format!(
+                        "{} is not in the current validator set",
+                        to
+                    )))
+                }
             }
 
-            validator_set.add(from, Some(weight));
+            // `from` stays in the set as it is - status, undelegations in flight and all
+            let to_stake = validator_set.take_stake(&from)?;
+
+            let cap = validator_set
+                .total_staked()
+                .saturating_add(to_stake)
+                .saturating_mul(policy.max_validator_share as u128)
+                / FEE_RESOLUTION;
+            let staked_at_target = validator_set.get(&to).map(|v| v.staked).unwrap_or_default();
+            if staked_at_target + to_stake > cap {
+                return Err(StdError::generic_err(format!(
+                    "Cannot redelegate to {} - it would go above the maximum validator share",
+                    to
+                )));
+            }
+
+            let mut messages: Vec<CosmosMsg> = vec![];
+            if to_stake > 0 {
+                validator_set.stake_at(&to, to_stake)?;
+                validator_set
+                    .mark_redelegation_target(&to, env.block.time + config.unbonding_time)?;
+                messages.push(redelegate_msg(&from, &to, to_stake));
+            }
+            validator_set.rebalance();
 
+            checkpoint_performance(&mut deps.storage, &validator_set, env.block.time)?;
             set_validator_set(&mut deps.storage, &validator_set)?;
 
             Ok(HandleResponse {
                 messages,
-                log: vec![],
+                log: vec![
+                    log("action", "redelegate"),
+                    log("from", from),
+                    log("to", to),
+                    log("amount", to_stake),
+                ],
                 data: None,
             })
         }
-        HandleMsg::KillSwitchUnbond {} => {
-            let frozen_exchange_rate = exchange_rate(&deps.storage, &deps.querier)?;
-            debug_print(format!("Frozen exchange rate at: {}", frozen_exchange_rate));
-            config.kill_switch = KillSwitch::Unbonding.into();
-            set_config(&mut deps.storage, &config);
+        HandleMsg::ReplaceValidatorSet { validators } => {
+            if validators.is_empty() {
+                return Err(StdError::generic_err(
+                    "The new validator set cannot be empty",
+                ));
+            }
 
-            store_frozen_exchange_rate(&mut deps.storage, &frozen_exchange_rate);
+            let chain_validators = deps.querier.query_validators()?;
+            let mut new_set: Vec<(String, u8)> = vec![];
+
+            for validator in validators {
+                if validator.weight == 0 {
+                    return Err(StdError::generic_err(format!(
+                        "Weight of {} must be greater than 0",
+                        validator.address
+                    )));
+                }
+                if new_set
+                    .iter()
+                    .any(|(address, _)| address == &validator.address)
+                {
+                    return Err(StdError::generic_err(format!(
+                        "{} appears more than once",
+                        validator.address
+                    )));
+                }
+                if !chain_validators
+                    .iter()
+                    .any(|v| v.address.0 == validator.address)
+                {
+                    return Err(StdError::generic_err(format!(
+                        "{} is not in the current validator set",
+                        validator.address
+                    )));
+                }
+                new_set.push((validator.address, validator.weight));
+            }
 
             let mut validator_set = get_validator_set(&deps.storage)?;
+            validator_set.replace(new_set)?;
 
-            let messages = validator_set.unbond_all();
-            validator_set.zero();
+            let max_share = read_validator_policy(&deps.storage)?.max_validator_share;
+            let (messages, mut logs) =
+                migration_step(&mut validator_set, &env, config.unbonding_time, max_share)?;
 
+            checkpoint_performance(&mut deps.storage, &validator_set, env.block.time)?;
             set_validator_set(&mut deps.storage, &validator_set)?;
 
+            logs.insert(0, log("action", "replace_validator_set"));
+
             Ok(HandleResponse {
                 messages,
-                log: vec![],
+                log: logs,
                 data: None,
             })
         }
 
-        HandleMsg::KillSwitchOpenWithdraws {} => {
-            config.kill_switch = KillSwitch::Open.into();
-            set_config(&mut deps.storage, &config);
-            Ok(HandleResponse::default())
-        }
+        HandleMsg::KillSwitchUnbond {} => start_wind_down(deps, env, config),
+
+        HandleMsg::KillSwitchOpenWithdraws {} => open_withdraws(deps, env, config),
 
         HandleMsg::RecoverToken {
             token,
@@ -246,11 +502,281 @@ pub fn admin_commands<S: Storage, A: Api, Q: Querier>(
             data: None,
         }),
 
-        HandleMsg::ChangeOwner { new_owner } => {
-            config.admin = new_owner;
+        HandleMsg::ProposeOwner { new_owner } => {
+            set_pending_owner(&mut deps.storage, &new_owner)?;
 
-            set_config(&mut deps.storage, &config);
-            Ok(HandleResponse::default())
+            Ok(HandleResponse {
+                messages: vec![],
+                log: vec![log(
+                    "pending_owner",
+                    new_owner.map(|o| o.0).unwrap_or_default(),
+                )],
+                data: None,
+            })
+        }
+
+        HandleMsg::SetTimelockDelay { delay } => {
+            let mut timelock = read_timelock(&deps.storage)?;
+            timelock.delay = delay;
+            set_timelock(&mut deps.storage, &timelock)?;
+
+            Ok(HandleResponse {
+                messages: vec![],
+                log: vec![log("delay", delay)],
+                data: None,
+            })
+        }
+
+        HandleMsg::ChangeValidatorPolicy {
+            max_commission,
+            drift_tolerance,
+            strategy,
+            max_validator_share,
+            max_preferred_share,
+        } => {
+            let mut policy = read_validator_policy(&deps.storage)?;
+
+            if let Some(max_commission) = max_commission {
+                policy.max_commission = max_commission;
+            }
+
+            if let Some(drift_tolerance) = drift_tolerance {
+                policy.drift_tolerance = drift_tolerance;
+            }
+
+            if let Some(strategy) = strategy {
+                policy.strategy = strategy.into();
+            }
+
+            if let Some(max_validator_share) = max_validator_share {
+                if max_validator_share == 0 || max_validator_share as u128 > FEE_RESOLUTION {
+                    return Err(StdError::generic_err(format!(
+                        "max_validator_share must be between 1 and {}",
+                        FEE_RESOLUTION
+                    )));
+                }
+                policy.max_validator_share = max_validator_share;
+            }
+
+            if let Some(max_preferred_share) = max_preferred_share {
+                if max_preferred_share as u128 > FEE_RESOLUTION {
+                    return Err(StdError::generic_err(format!(
+                        "max_preferred_share must be at most {}",
+                        FEE_RESOLUTION
+                    )));
+                }
+                policy.max_preferred_share = max_preferred_share;
+            }
+
+            set_validator_policy(&mut deps.storage, &policy)?;
+
+            Ok(HandleResponse {
+                messages: vec![],
+                log: vec![
+                    log("max_commission", format!("{:?}", policy.max_commission)),
+                    log("drift_tolerance", format!("{:?}", policy.drift_tolerance)),
+                    log("strategy", format!("{:?}", policy.strategy)),
+                    log(
+                        "max_validator_share",
+                        format!("{:?}", policy.max_validator_share),
+                    ),
+                    log(
+                        "max_preferred_share",
+                        format!("{:?}", policy.max_preferred_share),
+                    ),
+                ],
+                data: None,
+            })
+        }
+
+        HandleMsg::SetScoringConfig {
+            oracle,
+            min_weight,
+            max_weight,
+        } => {
+            if min_weight == 0 || min_weight > max_weight {
+                return Err(StdError::generic_err(
+                    "Weight bounds must satisfy 0 < min_weight <= max_weight",
+                ));
+            }
+
+            set_scoring_config(
+                &mut deps.storage,
+                &ScoringConfig {
+                    oracle,
+                    min_weight,
+                    max_weight,
+                },
+            )?;
+
+            Ok(HandleResponse {
+                messages: vec![],
+                log: vec![log("min_weight", min_weight), log("max_weight", max_weight)],
+                data: None,
+            })
+        }
+
+        HandleMsg::SetCompoundBounty { bounty } => {
+            if bounty > MAX_COMPOUND_BOUNTY {
+                return Err(StdError::generic_err(format!(
+                    "Compound bounty cannot be more than {}",
+                    MAX_COMPOUND_BOUNTY
+                )));
+            }
+
+            let mut compounding = read_compounding(&deps.storage)?;
+            compounding.bounty = bounty;
+            set_compounding(&mut deps.storage, &compounding)?;
+
+            Ok(HandleResponse {
+                messages: vec![],
+                log: vec![log("bounty", bounty)],
+                data: None,
+            })
+        }
+
+        HandleMsg::AddRateSubscriber { contract } => {
+            let mut subscribers = read_rate_subscribers(&deps.storage)?;
+
+            if subscribers
+                .contracts
+                .iter()
+                .any(|c| c.address == contract.address)
+            {
+                return Err(StdError::generic_err(format!(
+                    "{} is already subscribed",
+                    contract.address
+                )));
+            }
+            if subscribers.contracts.len() >= MAX_RATE_SUBSCRIBERS {
+                return Err(StdError::generic_err(format!(
+                    "Cannot have more than {} rate subscribers",
+                    MAX_RATE_SUBSCRIBERS
+                )));
+            }
+
+            subscribers.contracts.push(contract.clone());
+            set_rate_subscribers(&mut deps.storage, &subscribers)?;
+
+            Ok(HandleResponse {
+                messages: vec![],
+                log: vec![log("subscribed", contract.address.as_str())],
+                data: None,
+            })
+        }
+
+        HandleMsg::RemoveRateSubscriber { address } => {
+            let mut subscribers = read_rate_subscribers(&deps.storage)?;
+
+            let before = subscribers.contracts.len();
+            subscribers.contracts.retain(|c| c.address != address);
+            if subscribers.contracts.len() == before {
+                return Err(StdError::generic_err(format!(
+                    "{} is not subscribed",
+                    address
+                )));
+            }
+
+            set_rate_subscribers(&mut deps.storage, &subscribers)?;
+
+            Ok(HandleResponse {
+                messages: vec![],
+                log: vec![log("unsubscribed", address.as_str())],
+                data: None,
+            })
+        }
+
+        HandleMsg::SetRateGuard {
+            max_change_per_window,
+            max_change_per_block,
+        } => {
+            let mut guard = read_rate_guard(&deps.storage)?;
+
+            if let Some(max_change_per_window) = max_change_per_window {
+                guard.max_change_per_window = max_change_per_window;
+            }
+            if let Some(max_change_per_block) = max_change_per_block {
+                guard.max_change_per_block = max_change_per_block;
+            }
+
+            set_rate_guard(&mut deps.storage, &guard)?;
+
+            Ok(HandleResponse {
+                messages: vec![],
+                log: vec![
+                    log("max_change_per_window", guard.max_change_per_window),
+                    log("max_change_per_block", guard.max_change_per_block),
+                ],
+                data: None,
+            })
+        }
+
+        HandleMsg::GrantRole { role, address } => {
+            let mut roles = read_roles(&deps.storage)?;
+            roles.grant(address.clone(), role);
+            set_roles(&mut deps.storage, &roles)?;
+
+            Ok(HandleResponse {
+                messages: vec![],
+                log: vec![
+                    log("action", "grant_role"),
+                    log("role", format!("{:?}", role)),
+                    log("address", address.as_str()),
+                ],
+                data: None,
+            })
+        }
+
+        HandleMsg::RevokeRole { role, address } => {
+            let mut roles = read_roles(&deps.storage)?;
+            if !roles.revoke(&address, role) {
+                return Err(StdError::generic_err(format!(
+                    "{} doesn't have the {:?} role",
+                    address, role
+                )));
+            }
+            set_roles(&mut deps.storage, &roles)?;
+
+            Ok(HandleResponse {
+                messages: vec![],
+                log: vec![
+                    log("action", "revoke_role"),
+                    log("role", format!("{:?}", role)),
+                    log("address", address.as_str()),
+                ],
+                data: None,
+            })
+        }
+
+        HandleMsg::SetInsuranceFeeShare { fee_share } => {
+            if fee_share > FEE_RESOLUTION as u64 {
+                return Err(StdError::generic_err(format!(
+                    "Insurance fee share cannot be more than {}",
+                    FEE_RESOLUTION
+                )));
+            }
+
+            let mut insurance = read_insurance_fund(&deps.storage)?;
+            insurance.fee_share = fee_share;
+            set_insurance_fund(&mut deps.storage, &insurance)?;
+
+            Ok(HandleResponse {
+                messages: vec![],
+                log: vec![log("fee_share", fee_share)],
+                data: None,
+            })
+        }
+
+        HandleMsg::Unpause { flags } => {
+            let mut paused = read_pause_flags(&deps.storage)?;
+            paused.unpause(&flags);
+            set_pause_flags(&mut deps.storage, &paused)?;
+
+            Ok(HandleResponse {
+                messages: vec![],
+                log: pause_logs("unpause", &env, &paused),
+                data: None,
+            })
         }
 
         HandleMsg::ChangeWeight { address, weight } => {
@@ -268,3 +794,32 @@ pub fn admin_commands<S: Storage, A: Api, Q: Querier>(
 }
 
 // pub fn handle_restake_rewards() {}
+
+/// The second step of an ownership transfer - the proposed owner takes over
+pub fn accept_owner<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> StdResult<HandleResponse> {
+    let pending = read_pending_owner(&deps.storage)?;
+    if pending.as_ref() != Some(&env.message.sender) {
+        return Err(StdError::generic_err(
+            "Only the proposed owner can accept the ownership",
+        ));
+    }
+
+    let mut config = read_config(&deps.storage)?;
+    let previous = config.admin.clone();
+    config.admin = env.message.sender.clone();
+    set_config(&mut deps.storage, &config);
+    set_pending_owner(&mut deps.storage, &None)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "accept_owner"),
+            log("previous_owner", previous.as_str()),
+            log("owner", env.message.sender.as_str()),
+        ],
+        data: None,
+    })
+}
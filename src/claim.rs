@@ -1,4 +1,6 @@
 //use crate::types::pending_withdraws::PendingWithdraws;
+use crate::pause::ensure_not_paused;
+use crate::types::claims_ledger::{read_claims_ledger, set_claims_ledger};
 use crate::types::user_withdraws::{all_waiting_withdraws_for_user, get_withdraw_for_user};
 use crate::types::user_withdraws::{
     get_active_withdraw_window, set_active_withdraw_window, UserWithdrawManager,
@@ -14,6 +16,8 @@ pub fn claim<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
 ) -> StdResult<HandleResponse> {
+    ensure_not_paused(&deps.storage, "Claims", |paused| paused.claims)?;
+
     let (sum_withdraws, messages) = _claim_withdraws_for_sender(deps, &env)?;
 
     let res = HandleResponse {
@@ -34,6 +38,8 @@ pub fn claim_multiple<S: Storage, A: Api, Q: Querier>(
     env: &Env,
     amount: u32,
 ) -> StdResult<HandleResponse> {
+    ensure_not_paused(&deps.storage, "Claims", |paused| paused.claims)?;
+
     let (sum_withdraws, messages) = _claim_multiple_withdraws(deps, env, amount)?;
 
     debug_print(format!(
@@ -139,6 +145,10 @@ fn _do_claim<S: Storage, A: Api, Q: Querier>(
             address
         ));
 
+        let mut claims = read_claims_ledger(&deps.storage)?;
+        claims.claim(window, coin.amount.u128());
+        set_claims_ledger(&mut deps.storage, &claims)?;
+
         messages.push(CosmosMsg::Bank(BankMsg::Send {
             from_address: env.contract.address.clone(),
             to_address: address.clone(),
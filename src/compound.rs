@@ -0,0 +1,131 @@
+use cosmwasm_std::{
+    log, Api, BankMsg, Coin, CosmosMsg, Env, Extern, HandleResponse, Querier, StdError, StdResult,
+    Storage, Uint128,
+};
+use std::cmp::min;
+
+use crate::constants::{FEE_RESOLUTION, MAX_COMPOUNDS_PER_TX};
+use crate::pause::ensure_not_paused;
+use crate::rate_oracle::publish_exchange_rate;
+use crate::staking::{get_rewards_by_validator, stake_msg, withdraw_to_self};
+use crate::types::compounding::{read_compounding, set_compounding};
+use crate::types::config::read_config;
+use crate::types::delegation_strategy::strategy_from_policy;
+use crate::types::pending_restake::take_matured_restake;
+use crate::types::validator_performance::{checkpoint_performance, record_harvest};
+use crate::types::validator_policy::read_validator_policy;
+use crate::types::validator_set::{get_validator_set, set_validator_set};
+use crate::types::yield_ledger::record_flows;
+use crate::validators::ensure_not_frozen;
+
+/// Harvests the pending rewards of up to `limit` validators, continuing from where the last call
+/// stopped, and restakes them through the normal placement logic. The caller gets the bounty
+/// share of the harvest
+pub fn try_compound<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    limit: Option<u32>,
+) -> StdResult<HandleResponse> {
+    let config = read_config(&deps.storage)?;
+    ensure_not_frozen(&config)?;
+    ensure_not_paused(&deps.storage, "Validator operations", |paused| {
+        paused.validator_ops
+    })?;
+
+    let mut compounding = read_compounding(&deps.storage)?;
+    let limit = min(limit.unwrap_or(MAX_COMPOUNDS_PER_TX), MAX_COMPOUNDS_PER_TX) as usize;
+    if limit == 0 {
+        return Err(StdError::generic_err("limit must be greater than 0"));
+    }
+
+    let mut rewards: Vec<(String, u128)> =
+        get_rewards_by_validator(&deps.querier, &env.contract.address)?
+            .into_iter()
+            .filter(|(_, amount)| *amount > 0)
+            .collect();
+    rewards.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut page: Vec<(String, u128)> = rewards
+        .iter()
+        .filter(|(address, _)| {
+            compounding
+                .cursor
+                .as_ref()
+                .map_or(true, |cursor| address > cursor)
+        })
+        .take(limit)
+        .cloned()
+        .collect();
+
+    // we're past the last validator with rewards - start over
+    if page.is_empty() {
+        page = rewards.into_iter().take(limit).collect();
+    }
+
+    let harvested: u128 = page.iter().map(|(_, amount)| amount).sum();
+    if harvested == 0 {
+        return Err(StdError::generic_err("There are no rewards to compound"));
+    }
+
+    let mut messages: Vec<CosmosMsg> = page
+        .iter()
+        .map(|(address, _)| withdraw_to_self(address))
+        .collect();
+
+    // recorded before the harvest is restaked, while it's still counted as pending rewards
+    messages.extend(publish_exchange_rate(
+        &mut deps.storage,
+        &deps.querier,
+        &env,
+    )?);
+
+    for (address, amount) in page.iter() {
+        record_harvest(&mut deps.storage, address, *amount, env.block.time)?;
+    }
+
+    let bounty = harvested.saturating_mul(compounding.bounty as u128) / FEE_RESOLUTION;
+    if bounty > 0 {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address.clone(),
+            to_address: env.message.sender.clone(),
+            amount: vec![Coin {
+                denom: "uscrt".to_string(),
+                amount: Uint128(bounty),
+            }],
+        }));
+    }
+
+    record_flows(&mut deps.storage, harvested, bounty, 0, 0)?;
+
+    let to_stake = harvested - bounty + take_matured_restake(&mut deps.storage, env.block.time)?;
+
+    let mut validator_set = get_validator_set(&deps.storage)?;
+    if to_stake > 0 {
+        let policy = read_validator_policy(&deps.storage)?;
+        let strategy = strategy_from_policy(&policy)?;
+        for (validator, amount) in
+            validator_set.delegate(strategy.as_ref(), to_stake, policy.max_validator_share)?
+        {
+            messages.push(stake_msg(&validator, amount));
+        }
+    }
+
+    checkpoint_performance(&mut deps.storage, &validator_set, env.block.time)?;
+    set_validator_set(&mut deps.storage, &validator_set)?;
+
+    compounding.cursor = page.last().map(|(address, _)| address.clone());
+    set_compounding(&mut deps.storage, &compounding)?;
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![
+            log("action", "compound"),
+            log("account", env.message.sender.as_str()),
+            log("validators", page.len()),
+            log("harvested", harvested),
+            log("bounty", bounty),
+            log("restaked", to_stake),
+        ],
+        data: None,
+    })
+}
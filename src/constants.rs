@@ -1,10 +1,20 @@
 pub const AMOUNT_OF_SHARED_WITHDRAWS: u32 = 5;
 pub const AMOUNT_OF_REWARDS_TO_HANDLE: u32 = 2;
+pub const MAX_REDELEGATIONS_PER_TX: u32 = 5;
+pub const MAX_PAGE_SIZE: u32 = 30;
+pub const MAX_COMPOUNDS_PER_TX: u32 = 10;
+pub const MAX_UNBONDS_PER_TX: u32 = 10;
+// -- the chain refuses more undelegations in flight per validator
+pub const MAX_UNBONDING_ENTRIES: usize = 7;
 // -- 21 days + 2 minutes (buffer to make sure unbond will be matured)
 //pub(crate) const UNBONDING_TIME: u64 = 3600 * 24 * 21 + 120;
 pub const UNBONDING_TIME: u64 = 80;
 pub const CASH_TOKEN_SYMBOL: &str = "dSCRT";
 pub const NATIVE_TOKEN_DENOM: &str = "uscrt";
+pub const FEE_RESOLUTION: u128 = 100_000;
+/// 5% - the dev fee comes out of every deposit, so it can never get close to the whole of it
+pub const MAX_DEV_FEE: u64 = 5_000;
+pub const SECONDS_PER_YEAR: u64 = 3600 * 24 * 365;
 
 // pub const NUM_OF_WINDOWS: u64 = 3; // number of windows = 7
 pub const WINDOW_TIME: u64 = 20; // time for each window = 3 days
@@ -6,28 +6,44 @@ use secret_toolkit::snip20;
 
 use cargo_common::tokens::{InitHook, TokenInitMsg};
 
-use crate::admin::admin_commands;
+use crate::admin::{accept_owner, admin_commands};
 use crate::claim::claim;
+use crate::compound::try_compound;
 use crate::deposit::try_deposit;
+use crate::migrate::migrate_state;
 use crate::msg::{HandleMsg, InitMsg, MigrateMsg, QueryMsg};
+use crate::pause::try_pause;
 use crate::queries::{
-    query_activation_fee, query_current_window, query_dev_fee, query_exchange_rate, query_info,
-    query_pending_claims,
+    query_activation_fee, query_apr, query_backing, query_current_window, query_dev_fee,
+    query_exchange_rate, query_exchange_rate_history, query_info, query_insurance_fund,
+    query_pause_flags, query_pending_claims, query_rate_guard, query_rate_subscribers,
+    query_rebalance_plan, query_roles, query_slash_history, query_solvency, query_timelock,
+    query_twap, query_validator_migration, query_validator_performance, query_validator_policy,
+    query_validator_scores, query_validators, query_wind_down,
 };
+use crate::rate_guard::{override_rate_guard, raise_rate_alert};
+use crate::scoring::refresh_validator_set;
 use crate::state::store_address;
 use crate::types::config::{read_config, set_config, Config};
+use crate::types::contract_version::{set_contract_version, CONTRACT_VERSION};
 use crate::types::killswitch::KillSwitch;
 use crate::types::shared_withdraw_config::SharedWithdrawConfig;
+use crate::types::validator_policy::{set_validator_policy, ValidatorPolicy};
 use crate::types::validator_set::{set_validator_set, ValidatorSet};
+use crate::validators::{
+    continue_validator_migration, enforce_validator_policy, evacuate_validators,
+    rebalance_validators, sync_delegations,
+};
 use crate::voting::try_vote;
 use crate::withdraw::try_withdraw;
 
-use crate::constants::{CASH_TOKEN_SYMBOL, UNBONDING_TIME, WINDOW_TIME};
+use crate::constants::{CASH_TOKEN_SYMBOL, MAX_DEV_FEE, UNBONDING_TIME, WINDOW_TIME};
 use crate::types::activation_fee::{
     set_activation_fee, set_activation_fee_config, ActivationFeeConfig,
 };
 use crate::types::user_withdraws::set_active_withdraw_window;
 use crate::types::window_manager::{set_window_manager, WindowManager};
+use crate::wind_down::continue_wind_down;
 use crate::window::advance_window;
 use rust_decimal::prelude::Zero;
 
@@ -62,6 +78,12 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
     )?;
     set_activation_fee(&mut deps.storage, &u64::zero())?;
 
+    let mut policy = ValidatorPolicy::default();
+    if let Some(max_commission) = msg.max_commission {
+        policy.max_commission = max_commission;
+    }
+    set_validator_policy(&mut deps.storage, &policy)?;
+
     // save the current address (used in queries because we don't actually know the address)
     store_address(&mut deps.storage, &env.contract.address);
 
@@ -72,6 +94,14 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
 
     set_active_withdraw_window(&mut deps.storage, &u64::zero())?;
 
+    let dev_fee = msg.dev_fee.unwrap_or(1000);
+    if dev_fee > MAX_DEV_FEE {
+        return Err(StdError::generic_err(format!(
+            "Dev fee cannot be more than {}",
+            MAX_DEV_FEE
+        )));
+    }
+
     let config = Config {
         admin: env.message.sender.clone(),
         token_contract: HumanAddr::default(),
@@ -83,7 +113,7 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
         unbonding_time: UNBONDING_TIME,
         //viewing_key: "yo".to_string(),
         kill_switch: KillSwitch::Closed.into(),
-        dev_fee: msg.dev_fee.unwrap_or(1000),
+        dev_fee,
         dev_address: msg.dev_address.unwrap_or_else(|| {
             HumanAddr("secret1lfhy2amwlxlu4usd4put9jm77v86gkd057gkhr".to_string())
         }),
@@ -91,6 +121,7 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
     };
 
     set_config(&mut deps.storage, &config);
+    set_contract_version(&mut deps.storage, CONTRACT_VERSION)?;
 
     let mut valset = ValidatorSet::default();
 
@@ -140,7 +171,9 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
     msg: HandleMsg,
 ) -> StdResult<HandleResponse> {
     match msg {
-        HandleMsg::Stake {} => try_deposit(deps, env),
+        HandleMsg::Stake {
+            validator_preference,
+        } => try_deposit(deps, env, validator_preference),
         HandleMsg::Receive {
             amount,
             sender,
@@ -153,6 +186,18 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
         //
         // }
         HandleMsg::AdvanceWindow {} => advance_window(deps, env),
+        HandleMsg::EnforceValidatorPolicy {} => enforce_validator_policy(deps, env),
+        HandleMsg::EvacuateValidators {} => evacuate_validators(deps, env),
+        HandleMsg::Rebalance { max_moves } => rebalance_validators(deps, env, max_moves),
+        HandleMsg::SyncDelegations {} => sync_delegations(deps, env),
+        HandleMsg::RefreshValidatorSet {} => refresh_validator_set(deps, env),
+        HandleMsg::ContinueValidatorMigration {} => continue_validator_migration(deps, env),
+        HandleMsg::Compound { limit } => try_compound(deps, env, limit),
+        HandleMsg::ContinueWindDown {} => continue_wind_down(deps, env),
+        HandleMsg::RaiseRateAlert {} => raise_rate_alert(deps, env),
+        HandleMsg::OverrideRateGuard {} => override_rate_guard(deps, env),
+        HandleMsg::Pause { flags } => try_pause(deps, env, flags),
+        HandleMsg::AcceptOwner {} => accept_owner(deps, env),
         _ => admin_commands(deps, env, msg),
     }
 }
@@ -173,6 +218,40 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
         QueryMsg::ActivationFee { current_time } => {
             query_activation_fee(&deps.storage, current_time)
         }
+        QueryMsg::ValidatorPolicy {} => query_validator_policy(&deps.storage),
+        QueryMsg::RebalancePlan {
+            max_moves,
+            current_time,
+        } => query_rebalance_plan(&deps.storage, max_moves, current_time),
+        QueryMsg::SlashHistory {} => query_slash_history(&deps.storage),
+        QueryMsg::ValidatorScores {} => query_validator_scores(&deps.storage, &deps.querier),
+        QueryMsg::ValidatorMigration { current_time } => {
+            query_validator_migration(&deps.storage, current_time)
+        }
+        QueryMsg::ValidatorPerformance { page, page_size } => {
+            query_validator_performance(&deps.storage, page, page_size)
+        }
+        QueryMsg::Apr { lookback_windows } => query_apr(&deps.storage, lookback_windows),
+        QueryMsg::Twap { period } => query_twap(&deps.storage, period),
+        QueryMsg::RateSubscribers {} => query_rate_subscribers(&deps.storage),
+        QueryMsg::RateGuard {} => query_rate_guard(&deps.storage),
+        QueryMsg::Backing { current_time } => {
+            query_backing(&deps.storage, &deps.querier, current_time)
+        }
+        QueryMsg::Solvency { current_time } => {
+            query_solvency(&deps.storage, &deps.querier, current_time)
+        }
+        QueryMsg::InsuranceFund {} => query_insurance_fund(&deps.storage),
+        QueryMsg::WindDown {} => query_wind_down(&deps.storage, &deps.querier),
+        QueryMsg::PauseFlags {} => query_pause_flags(&deps.storage),
+        QueryMsg::Roles {} => query_roles(&deps.storage),
+        QueryMsg::Timelock {} => query_timelock(&deps.storage),
+        QueryMsg::ExchangeRateHistory { start_after, limit } => {
+            query_exchange_rate_history(&deps.storage, start_after, limit)
+        }
+        QueryMsg::Validators { start_after, limit } => {
+            query_validators(&deps.storage, &deps.querier, start_after, limit)
+        }
     }
 }
 
@@ -217,9 +296,21 @@ pub fn post_initialize<S: Storage, A: Api, Q: Querier>(
 }
 
 pub fn migrate<S: Storage, A: Api, Q: Querier>(
-    _deps: &mut Extern<S, A, Q>,
-    _env: Env,
-    _msg: MigrateMsg,
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    msg: MigrateMsg,
 ) -> StdResult<MigrateResponse> {
-    Ok(MigrateResponse::default())
+    let mut logs = vec![log("action", "migrate")];
+    logs.extend(migrate_state(
+        &mut deps.storage,
+        &deps.querier,
+        &env,
+        msg.from_version(),
+    )?);
+
+    Ok(MigrateResponse {
+        messages: vec![],
+        log: logs,
+        data: None,
+    })
 }
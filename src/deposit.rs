@@ -8,22 +8,29 @@ use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use secret_toolkit::snip20;
 
-use crate::constants::AMOUNT_OF_REWARDS_TO_HANDLE;
-use crate::staking::{exchange_rate, get_rewards_limited, stake_msg};
+use crate::constants::{AMOUNT_OF_REWARDS_TO_HANDLE, FEE_RESOLUTION, NATIVE_TOKEN_DENOM};
+use crate::pause::ensure_not_paused;
+use crate::rate_guard::ensure_exchange_rate;
+use crate::staking::{exchange_rate, get_rewards_limited, sent_scrt, stake_msg};
 use crate::types::activation_fee::{
     read_activation_fee, read_activation_fee_config, set_activation_fee,
 };
 use crate::types::config::read_config;
+use crate::types::delegation_strategy::strategy_from_policy;
+use crate::types::insurance::{read_insurance_fund, set_insurance_fund};
 use crate::types::killswitch::KillSwitch;
+use crate::types::pending_restake::take_matured_restake;
+use crate::types::validator_performance::{checkpoint_performance, record_harvest};
+use crate::types::validator_policy::read_validator_policy;
 use crate::types::validator_set::{get_validator_set, set_validator_set};
+use crate::types::yield_ledger::record_flows;
 use crate::utils::perform_helper_claims;
 use std::cmp::min;
 
-const FEE_RESOLUTION: u128 = 100_000;
-
 pub fn try_deposit<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
+    validator_preference: Option<String>,
 ) -> StdResult<HandleResponse> {
     let mut amount_raw: Uint128 = Uint128::default();
     let config = read_config(&deps.storage)?;
@@ -38,8 +45,10 @@ pub fn try_deposit<S: Storage, A: Api, Q: Querier>(
         ));
     }
 
+    ensure_not_paused(&deps.storage, "Deposits", |paused| paused.deposits)?;
+
     for coin in &env.message.sent_funds {
-        if coin.denom == "uscrt" {
+        if coin.denom == NATIVE_TOKEN_DENOM {
             amount_raw = coin.amount
         }
     }
@@ -56,11 +65,17 @@ pub fn try_deposit<S: Storage, A: Api, Q: Querier>(
         ));
     }
 
-    perform_helper_claims(deps, &env, &config, &mut messages)?;
+    ensure_exchange_rate(&mut deps.storage, &deps.querier, &env)?;
 
-    let exch_rate = exchange_rate(&deps.storage, &deps.querier)?;
+    // priced before the helper claims - they leave the claims ledger before the SCRT leaves our
+    // balance
+    let exch_rate = exchange_rate(&deps.storage, &deps.querier, sent_scrt(&env))?;
 
+    perform_helper_claims(deps, &env, &config, &mut messages)?;
+
+    let deposited = amount_raw.u128();
     let mut fee = calc_fee(amount_raw, config.dev_fee);
+    let dev_fee = fee;
     amount_raw = Uint128::from(amount_raw.u128().saturating_sub(fee as u128));
 
     // calc activation fee
@@ -83,6 +98,14 @@ pub fn try_deposit<S: Storage, A: Api, Q: Querier>(
 
         set_activation_fee(&mut deps.storage, &fee_for_activation)?;
     }
+    let mut insurance = read_insurance_fund(&deps.storage)?;
+    if insurance.fee_share > 0 {
+        // the reserve stays in our balance - it's left out of the backing until it's paid out
+        let to_reserve = min(dev_fee * insurance.fee_share as u128 / FEE_RESOLUTION, fee);
+        fee -= to_reserve;
+        insurance.reserve += Uint128(to_reserve);
+        set_insurance_fund(&mut deps.storage, &insurance)?;
+    }
     debug_print(format!("fee after: {}", fee));
     messages.push(CosmosMsg::Bank(BankMsg::Send {
         from_address: env.contract.address.clone(),
@@ -117,7 +140,7 @@ pub fn try_deposit<S: Storage, A: Api, Q: Querier>(
     //     .unwrap_or_default()
     //     .u128()
     //     + amount_raw.u128();
-    let deposit_amount = top_5_rewards
+    let harvested = top_5_rewards
         .total
         .first()
         .unwrap_or(&Coin {
@@ -125,8 +148,24 @@ pub fn try_deposit<S: Storage, A: Api, Q: Querier>(
             amount: Default::default(),
         })
         .amount
-        .u128()
-        + amount_raw.u128();
+        .u128();
+    let deposit_amount =
+        harvested + amount_raw.u128() + take_matured_restake(&mut deps.storage, env.block.time)?;
+    record_flows(&mut deps.storage, harvested, dev_fee, deposited, 0)?;
+
+    for reward in top_5_rewards.rewards.iter() {
+        let amount = reward
+            .reward
+            .first()
+            .map(|c| c.amount.u128())
+            .unwrap_or_default();
+        record_harvest(
+            &mut deps.storage,
+            &reward.validator_address.0,
+            amount,
+            env.block.time,
+        )?;
+    }
 
     let top_5_validators = top_5_rewards
         .rewards
@@ -135,13 +174,37 @@ pub fn try_deposit<S: Storage, A: Api, Q: Querier>(
         .collect();
     messages.append(&mut validator_set.withdraw_rewards_messages(Some(top_5_validators)));
 
-    // add the amount to our stake tracker
-    let validator = validator_set.stake(deposit_amount)?;
-    validator_set.rebalance();
+    let policy = read_validator_policy(&deps.storage)?;
+    let mut to_delegate = deposit_amount;
+
+    // only the depositor's own SCRT follows the preference - rewards are shared by everyone
+    if let Some(validator) = &validator_preference {
+        let preferred = validator_set.delegate_preferred(
+            validator,
+            amount_raw.u128(),
+            policy.max_preferred_share,
+            policy.max_validator_share,
+            validator_set.total_staked() + deposit_amount,
+        )?;
+        if preferred > 0 {
+            messages.push(stake_msg(validator, preferred));
+            to_delegate -= preferred;
+        }
+    }
 
-    // send the stake message
-    messages.push(stake_msg(&validator, deposit_amount));
+    // add the amount to our stake tracker
+    if to_delegate > 0 {
+        let strategy = strategy_from_policy(&policy)?;
+        let allocations =
+            validator_set.delegate(strategy.as_ref(), to_delegate, policy.max_validator_share)?;
+
+        // send the stake messages
+        for (validator, amount) in allocations {
+            messages.push(stake_msg(&validator, amount));
+        }
+    }
 
+    checkpoint_performance(&mut deps.storage, &validator_set, env.block.time)?;
     set_validator_set(&mut deps.storage, &validator_set)?;
 
     Ok(HandleResponse {
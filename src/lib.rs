@@ -2,24 +2,35 @@
 
 mod admin;
 mod claim;
+mod compound;
 mod constants;
 pub mod contract;
 mod deposit;
+mod migrate;
+#[cfg(test)]
+mod mock_querier;
 pub mod msg;
+mod pause;
 mod queries;
+mod rate_guard;
+mod rate_oracle;
+mod scoring;
+mod solvency;
 mod staking;
 pub mod state;
 pub mod tokens;
 mod types;
 mod utils;
+mod validators;
 mod voting;
+mod wind_down;
 mod window;
 mod withdraw;
 
 #[cfg(target_arch = "wasm32")]
 mod wasm {
     use cosmwasm_std::{
-        do_handle, do_init, do_query, ExternalApi, ExternalQuerier, ExternalStorage,
+        do_handle, do_init, do_migrate, do_query, ExternalApi, ExternalQuerier, ExternalStorage,
     };
 
     use super::contract;
@@ -42,6 +53,15 @@ mod wasm {
         )
     }
 
+    #[no_mangle]
+    extern "C" fn migrate(env_ptr: u32, msg_ptr: u32) -> u32 {
+        do_migrate(
+            &contract::migrate::<ExternalStorage, ExternalApi, ExternalQuerier>,
+            env_ptr,
+            msg_ptr,
+        )
+    }
+
     #[no_mangle]
     extern "C" fn query(msg_ptr: u32) -> u32 {
         do_query(
@@ -0,0 +1,423 @@
+use cosmwasm_std::{log, Env, LogAttribute, Querier, StdError, StdResult, Storage};
+use cosmwasm_storage::ReadonlySingleton;
+
+use crate::types::claims_ledger::{set_claims_ledger, ClaimsLedger, KEY_CLAIMS_LEDGER};
+use crate::types::config::read_config;
+use crate::types::contract_version::{
+    read_contract_version, set_contract_version, CONTRACT_VERSION,
+};
+use crate::types::exchange_rate_history::{read_exchange_rate_history, MAX_EXCHANGE_RATE_HISTORY};
+use crate::types::legacy_validator_set::load_legacy_validator_set;
+use crate::types::user_withdraws::{
+    all_waiting_withdraws_for_user, get_active_withdraw_window, UserWithdrawManager,
+};
+use crate::types::validator_set::{get_validator_set, set_validator_set};
+use crate::types::window_manager::get_window_manager;
+use crate::types::withdraw_window::get_claim_time;
+use crate::wind_down::snapshot_legacy_wind_down;
+
+/// Upgrades the state from the layout of version `from` to the current one. Contracts deployed
+/// before the version was stored have to name their layout, the others have to name the stored one.
+/// A wind-down that was already under way gets its snapshot
+pub fn migrate_state<S: Storage, Q: Querier>(
+    storage: &mut S,
+    querier: &Q,
+    env: &Env,
+    from: u32,
+) -> StdResult<Vec<LogAttribute>> {
+    match read_contract_version(storage)? {
+        Some(version) if version == CONTRACT_VERSION => {
+            return Err(StdError::generic_err(format!(
+                "The state is already at version {}",
+                CONTRACT_VERSION
+            )));
+        }
+        Some(version) if version != from => {
+            return Err(StdError::generic_err(format!(
+                "The state is at version {}, not {}",
+                version, from
+            )));
+        }
+        _ => {}
+    }
+
+    if from == 0 || from > CONTRACT_VERSION {
+        return Err(StdError::generic_err(format!(
+            "Can't migrate from version {}",
+            from
+        )));
+    }
+
+    let mut logs = vec![
+        log("from_version", from),
+        log("to_version", CONTRACT_VERSION),
+    ];
+
+    if from == 1 {
+        let validator_set = load_legacy_validator_set(storage)?;
+        logs.push(log("validators", validator_set.len()));
+        set_validator_set(storage, &validator_set)?;
+    }
+
+    // everything stored as bincode has to load in the current layout before we sign off on it
+    read_config(storage)?;
+    get_window_manager(storage)?;
+    get_validator_set(storage)?;
+    read_exchange_rate_history(storage, None, MAX_EXCHANGE_RATE_HISTORY)?;
+
+    if let Some(windows) = seed_claims_ledger(storage)? {
+        logs.push(log("seeded_windows", windows));
+    }
+
+    if let Some(wind_down) = snapshot_legacy_wind_down(storage, querier, env)? {
+        logs.push(log("wind_down_pool", wind_down.pool));
+    }
+
+    set_contract_version(storage, CONTRACT_VERSION)?;
+
+    Ok(logs)
+}
+
+/// Contracts from before the claims ledger owe their closed windows without a record of it. The
+/// ledger is rebuilt from the withdraws nobody claimed yet. What the windows unbonded originally
+/// isn't known anymore, so `amount` starts out as what's outstanding
+fn seed_claims_ledger<S: Storage>(storage: &mut S) -> StdResult<Option<usize>> {
+    let existing: Option<ClaimsLedger> =
+        ReadonlySingleton::new(storage, KEY_CLAIMS_LEDGER).may_load()?;
+    if existing.is_some() {
+        return Ok(None);
+    }
+
+    let window_manager = get_window_manager(storage)?;
+    let first_unclaimed = get_active_withdraw_window(storage)?;
+
+    let mut ledger = ClaimsLedger::default();
+    for window in first_unclaimed..window_manager.current_active_window {
+        let claim_time = match get_claim_time(storage, window) {
+            Some(time) => time,
+            None => continue,
+        };
+
+        let mut outstanding = 0u128;
+        for user in UserWithdrawManager::new(window).users(storage)? {
+            outstanding += all_waiting_withdraws_for_user(storage, &user)
+                .0
+                .iter()
+                .filter(|w| w.id == window)
+                .map(|w| w.coins.amount.u128())
+                .sum::<u128>();
+        }
+
+        ledger.close_window(window, outstanding, claim_time);
+    }
+
+    set_claims_ledger(storage, &ledger)?;
+
+    Ok(Some(ledger.windows.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_env, MockStorage};
+    use cosmwasm_std::{HumanAddr, ReadonlyStorage, Uint128};
+    use cosmwasm_storage::{PrefixedStorage, ReadonlyPrefixedStorage};
+    use rust_decimal::prelude::FromStr;
+    use rust_decimal::Decimal;
+    use std::collections::VecDeque;
+
+    use crate::mock_querier::ChainQuerier;
+    use crate::state::{store_address, store_frozen_exchange_rate};
+    use crate::types::claims_ledger::{read_claims_ledger, WindowClaims};
+    use crate::types::config::{set_config, Config, PREFIX_CONFIG};
+    use crate::types::killswitch::KillSwitch;
+    use crate::types::user_withdraws::set_active_withdraw_window;
+    use crate::types::validator_set::{Validator, ValidatorSet, KEY_VALIDATOR_SET};
+    use crate::types::wind_down::{read_wind_down, ExpectedArrival};
+    use crate::types::window_manager::{set_window_manager, WindowManager};
+    use crate::types::withdraw_window::set_claim_time;
+    use crate::wind_down::wind_down_payout;
+
+    // bincode: little endian integers, u64 length prefixes for strings and sequences
+
+    fn encode_u64(bytes: &mut Vec<u8>, value: u64) {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn encode_u128(bytes: &mut Vec<u8>, value: u128) {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn encode_string(bytes: &mut Vec<u8>, value: &str) {
+        encode_u64(bytes, value.len() as u64);
+        bytes.extend_from_slice(value.as_bytes());
+    }
+
+    fn store_raw_validator_set<S: Storage>(storage: &mut S, bytes: &[u8]) {
+        let mut config_store = PrefixedStorage::new(PREFIX_CONFIG, storage);
+        config_store.set(KEY_VALIDATOR_SET, bytes);
+    }
+
+    /// a contract that holds no SCRT and never minted - nothing is asked of it unless the kill
+    /// switch was pulled
+    fn idle_chain() -> ChainQuerier {
+        ChainQuerier {
+            balance: 0,
+            delegations: vec![],
+            supply: 0,
+        }
+    }
+
+    fn v1_validator_set(staked: u128) -> Vec<u8> {
+        let mut bytes = vec![];
+        encode_u64(&mut bytes, 1);
+        encode_string(&mut bytes, "secretvaloper1a");
+        encode_u128(&mut bytes, staked);
+        bytes.push(10);
+        bytes
+    }
+
+    fn set_kill_switch<S: Storage>(storage: &mut S, kill_switch: KillSwitch) {
+        let mut config = read_config(storage).unwrap();
+        config.kill_switch = kill_switch.into();
+        set_config(storage, &config);
+    }
+
+    fn store_common_state<S: Storage>(storage: &mut S) {
+        store_address(storage, &HumanAddr("contract".to_string()));
+        set_config(
+            storage,
+            &Config {
+                admin: HumanAddr("admin".to_string()),
+                token_contract: HumanAddr("token".to_string()),
+                token_contract_hash: "hash".to_string(),
+                gov_token: HumanAddr("gov".to_string()),
+                gov_token_hash: "hash".to_string(),
+                voting_admin: HumanAddr("admin".to_string()),
+                unbonding_time: 1_814_400,
+                kill_switch: 0,
+                dev_address: HumanAddr("dev".to_string()),
+                dev_fee: 1000,
+                shared_withdrawals: 0,
+            },
+        );
+
+        let mut window_manager = WindowManager::default();
+        window_manager.window.coins.denom = "uscrt".to_string();
+        set_window_manager(storage, &window_manager).unwrap();
+        set_active_withdraw_window(storage, &0).unwrap();
+    }
+
+    fn validator(address: &str, staked: u128, weight: u8) -> Validator {
+        Validator {
+            address: address.to_string(),
+            staked,
+            weight,
+            status: 0,
+            redelegation_cooldown: 0,
+            preferred: 0,
+            unbonding: vec![],
+            last_sync: 0,
+        }
+    }
+
+    #[test]
+    fn test_migrate_v1_validator_set() {
+        let mut storage = MockStorage::new();
+        store_common_state(&mut storage);
+
+        // { validators: [{ address, staked, weight }] }
+        let mut bytes = vec![];
+        encode_u64(&mut bytes, 2);
+        encode_string(&mut bytes, "secretvaloper1a");
+        encode_u128(&mut bytes, 1_000_000);
+        bytes.push(10);
+        encode_string(&mut bytes, "secretvaloper1b");
+        encode_u128(&mut bytes, 500);
+        bytes.push(20);
+        store_raw_validator_set(&mut storage, &bytes);
+
+        assert!(get_validator_set(&storage).is_err());
+
+        migrate_state(&mut storage, &idle_chain(), &mock_env("admin", &[]), 1).unwrap();
+
+        let expected = ValidatorSet::from_parts(
+            VecDeque::from(vec![
+                validator("secretvaloper1a", 1_000_000, 10),
+                validator("secretvaloper1b", 500, 20),
+            ]),
+            0,
+        );
+        assert_eq!(get_validator_set(&storage).unwrap(), expected);
+        assert_eq!(
+            read_contract_version(&storage).unwrap(),
+            Some(CONTRACT_VERSION)
+        );
+    }
+
+    #[test]
+    fn test_migrate_rejects_wrong_layout() {
+        let mut storage = MockStorage::new();
+        store_common_state(&mut storage);
+
+        // a v1 validator that's missing its weight
+        let mut bytes = vec![];
+        encode_u64(&mut bytes, 1);
+        encode_string(&mut bytes, "secretvaloper1a");
+        encode_u128(&mut bytes, 1_000_000);
+        store_raw_validator_set(&mut storage, &bytes);
+
+        assert!(migrate_state(&mut storage, &idle_chain(), &mock_env("admin", &[]), 1).is_err());
+        assert_eq!(read_contract_version(&storage).unwrap(), None);
+        // nothing was overwritten
+        let config_store = ReadonlyPrefixedStorage::new(PREFIX_CONFIG, &storage);
+        assert_eq!(config_store.get(KEY_VALIDATOR_SET), Some(bytes));
+    }
+
+    #[test]
+    fn test_migrate_checks_stored_version() {
+        let mut storage = MockStorage::new();
+        store_common_state(&mut storage);
+        set_validator_set(&mut storage, &ValidatorSet::default()).unwrap();
+
+        let env = mock_env("admin", &[]);
+        assert!(migrate_state(&mut storage, &idle_chain(), &env, CONTRACT_VERSION + 1).is_err());
+
+        set_contract_version(&mut storage, CONTRACT_VERSION).unwrap();
+        assert!(migrate_state(&mut storage, &idle_chain(), &env, 1).is_err());
+        assert!(migrate_state(&mut storage, &idle_chain(), &env, CONTRACT_VERSION).is_err());
+    }
+
+    #[test]
+    fn test_migrate_seeds_claims_ledger() {
+        let mut storage = MockStorage::new();
+        store_common_state(&mut storage);
+        store_raw_validator_set(&mut storage, &0u64.to_le_bytes());
+
+        let alice = HumanAddr("alice".to_string());
+        let bob = HumanAddr("bob".to_string());
+
+        let mut window_manager = get_window_manager(&storage).unwrap();
+        for &(user, amount) in &[(&alice, 100u128), (&bob, 50)] {
+            window_manager
+                .withdraw(&mut storage, user, Uint128(amount))
+                .unwrap();
+            UserWithdrawManager::new(0)
+                .append(&mut storage, user)
+                .unwrap();
+        }
+        window_manager.advance_window(1_000).unwrap();
+        set_claim_time(&mut storage, 0, 2_000).unwrap();
+
+        window_manager
+            .withdraw(&mut storage, &alice, Uint128(30))
+            .unwrap();
+        UserWithdrawManager::new(1)
+            .append(&mut storage, &alice)
+            .unwrap();
+        set_window_manager(&mut storage, &window_manager).unwrap();
+
+        migrate_state(&mut storage, &idle_chain(), &mock_env("admin", &[]), 1).unwrap();
+
+        // the open window isn't owed yet - it's still counted in the window manager
+        assert_eq!(
+            read_claims_ledger(&storage).unwrap().windows,
+            vec![WindowClaims {
+                window: 0,
+                claim_time: 2_000,
+                amount: Uint128(150),
+                outstanding: Uint128(150),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_migrate_snapshots_an_unbonding_wind_down() {
+        let mut storage = MockStorage::new();
+        store_common_state(&mut storage);
+        set_kill_switch(&mut storage, KillSwitch::Unbonding);
+        // everything was unbonded at once, at 2 tokens per SCRT
+        store_raw_validator_set(&mut storage, &v1_validator_set(0));
+        store_frozen_exchange_rate(&mut storage, &Decimal::from_str("2").unwrap());
+
+        let querier = ChainQuerier {
+            balance: 100_000,
+            delegations: vec![],
+            supply: 1_000_000,
+        };
+        let env = mock_env("admin", &[]);
+        migrate_state(&mut storage, &querier, &env, 1).unwrap();
+
+        let wind_down = read_wind_down(&storage).unwrap().unwrap();
+        assert_eq!(wind_down.supply, Uint128(1_000_000));
+        assert_eq!(wind_down.pool, Uint128(500_000));
+        // withdraws stay closed until a full unbonding period has passed
+        assert_eq!(
+            wind_down.arrivals,
+            vec![ExpectedArrival {
+                validator: None,
+                amount: Uint128(400_000),
+                available_time: env.block.time + 1_814_400,
+            }]
+        );
+
+        // until then the payouts are capped by what arrived
+        let contract = HumanAddr("contract".to_string());
+        assert_eq!(
+            wind_down_payout(&storage, &querier, &contract, Uint128(500_000)).unwrap(),
+            50_000
+        );
+        let querier = ChainQuerier {
+            balance: 500_000,
+            ..querier
+        };
+        assert_eq!(
+            wind_down_payout(&storage, &querier, &contract, Uint128(500_000)).unwrap(),
+            250_000
+        );
+    }
+
+    #[test]
+    fn test_migrate_snapshots_an_open_wind_down() {
+        let mut storage = MockStorage::new();
+        store_common_state(&mut storage);
+        set_kill_switch(&mut storage, KillSwitch::Open);
+        store_raw_validator_set(&mut storage, &v1_validator_set(0));
+
+        // a withdraw that was still in the open window is queued like the others
+        let alice = HumanAddr("alice".to_string());
+        let mut window_manager = get_window_manager(&storage).unwrap();
+        window_manager
+            .withdraw(&mut storage, &alice, Uint128(100_000))
+            .unwrap();
+        UserWithdrawManager::new(0)
+            .append(&mut storage, &alice)
+            .unwrap();
+        set_window_manager(&mut storage, &window_manager).unwrap();
+
+        let querier = ChainQuerier {
+            balance: 1_600_000,
+            delegations: vec![],
+            supply: 1_000_000,
+        };
+        let env = mock_env("admin", &[]);
+        migrate_state(&mut storage, &querier, &env, 1).unwrap();
+
+        assert_eq!(
+            read_claims_ledger(&storage).unwrap().total_outstanding(),
+            100_000
+        );
+
+        let wind_down = read_wind_down(&storage).unwrap().unwrap();
+        assert_eq!(wind_down.pool, Uint128(1_500_000));
+        assert_eq!(wind_down.claims, Uint128(100_000));
+        assert!(wind_down.arrivals.is_empty());
+
+        let contract = HumanAddr("contract".to_string());
+        assert_eq!(
+            wind_down_payout(&storage, &querier, &contract, Uint128(200_000)).unwrap(),
+            300_000
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use serde::Serialize;
+
+use cosmwasm_std::{
+    from_slice, to_binary, AllDelegationsResponse, BalanceResponse, BankQuery, Coin, Delegation,
+    DistQuery, Empty, HumanAddr, Querier, QuerierResult, QueryRequest, RewardsResponse,
+    StakingQuery, StdError, Uint128, WasmQuery,
+};
+use secret_toolkit::snip20::TokenInfo;
+
+#[derive(Serialize)]
+struct TokenInfoResponse {
+    token_info: TokenInfo,
+}
+
+/// Answers like the chain and the token contract would: the contract's balance, its delegations,
+/// no pending rewards and the token supply
+pub struct ChainQuerier {
+    pub balance: u128,
+    pub delegations: Vec<(&'static str, u128)>,
+    pub supply: u128,
+}
+
+impl Querier for ChainQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        let request: QueryRequest<Empty> = from_slice(bin_request).unwrap();
+        Ok(match request {
+            QueryRequest::Bank(BankQuery::Balance { denom, .. }) => to_binary(&BalanceResponse {
+                amount: Coin {
+                    denom,
+                    amount: Uint128(self.balance),
+                },
+            }),
+            QueryRequest::Staking(StakingQuery::AllDelegations { delegator }) => {
+                to_binary(&AllDelegationsResponse {
+                    delegations: self
+                        .delegations
+                        .iter()
+                        .map(|(validator, amount)| Delegation {
+                            delegator: delegator.clone(),
+                            validator: HumanAddr(validator.to_string()),
+                            amount: Coin {
+                                denom: "uscrt".to_string(),
+                                amount: Uint128(*amount),
+                            },
+                        })
+                        .collect(),
+                })
+            }
+            QueryRequest::Dist(DistQuery::Rewards { .. }) => to_binary(&RewardsResponse {
+                rewards: vec![],
+                total: vec![],
+            }),
+            QueryRequest::Wasm(WasmQuery::Smart { .. }) => to_binary(&TokenInfoResponse {
+                token_info: TokenInfo {
+                    name: "dSCRT".to_string(),
+                    symbol: "DSCRT".to_string(),
+                    decimals: 6,
+                    total_supply: Some(Uint128(self.supply)),
+                },
+            }),
+            _ => Err(StdError::generic_err("unsupported query")),
+        })
+    }
+}
@@ -4,8 +4,21 @@ use serde::{Deserialize, Serialize};
 
 use cargo_common::contract::Contract;
 
+use crate::scoring::ValidatorScore;
+use crate::solvency::Discrepancy;
+use crate::types::delegation_strategy::DelegationStrategyKind;
+use crate::types::exchange_rate_history::ExchangeRatePoint;
+use crate::types::insurance::InsurancePayout;
+use crate::types::pause::PauseFlags;
 use crate::types::pending_withdraw::PendingWithdraw;
-use crate::types::validator_set::ValidatorResponse;
+use crate::types::rate_guard::{RateAlert, RateReference};
+use crate::types::roles::{Role, RoleMember};
+use crate::types::slashing::SlashEvent;
+use crate::types::timelock::QueuedAction;
+use crate::types::validator_performance::ValidatorPerformance;
+use crate::types::validator_set::{PlannedMove, ValidatorDetails, ValidatorResponse};
+use crate::types::wind_down::WindDown;
+use crate::types::yield_ledger::RateSnapshot;
 
 #[derive(Serialize, Deserialize, JsonSchema)]
 pub struct InitMsg {
@@ -18,13 +31,18 @@ pub struct InitMsg {
     pub dev_address: Option<HumanAddr>,
     pub activation_fee: Option<u64>,
     pub activation_fee_max: Option<u64>,
+    pub max_commission: Option<u64>,
 }
 
-#[derive(Serialize, Deserialize, JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum HandleMsg {
     /// user interactions
-    Stake {},
+    /// validator_preference places the deposit on that validator (it has to be in the validator
+    /// set), up to the preference cap. The minted tokens are the same either way
+    Stake {
+        validator_preference: Option<String>,
+    },
     Claim {},
 
     /// token interaction
@@ -39,6 +57,56 @@ pub enum HandleMsg {
 
     AdvanceWindow {},
 
+    /// evict every validator whose commission is above the configured maximum, and redelegate
+    /// its stake to the rest of the set
+    EnforceValidatorPolicy {},
+
+    /// refresh the on-chain status of every validator in the set, and redelegate stake away from
+    /// validators that are jailed or no longer in the active set
+    EvacuateValidators {},
+
+    /// redelegate stake towards the target weights of the set. max_moves is capped by the
+    /// contract, and defaults to the cap
+    Rebalance {
+        max_moves: Option<u32>,
+    },
+
+    /// compare our bookkeeping with the real delegations, and record any slashing
+    SyncDelegations {},
+
+    /// re-score the validators in the set and adjust their weights within the admin-set bounds
+    RefreshValidatorSet {},
+
+    /// run the next batch of redelegations of a validator set replacement
+    ContinueValidatorMigration {},
+
+    /// unbonds the next batch during an emergency wind-down, and opens withdraws once all the
+    /// stake has arrived
+    ContinueWindDown {},
+
+    /// harvest and restake the rewards of the next `limit` validators with pending rewards. The
+    /// caller is paid a bounty out of the harvest. limit is capped by the contract, and defaults
+    /// to the cap
+    Compound {
+        limit: Option<u32>,
+    },
+
+    /// deposits and withdraws that see the exchange rate move out of the bounds of the rate guard
+    /// are reverted. This stores the alert, halting them until it's overridden. Permissionless,
+    /// and fails if the rate is within the bounds
+    RaiseRateAlert {},
+
+    /// accept the current exchange rate after a rate alert. Guardian or admin only
+    OverrideRateGuard {},
+
+    /// pauses the parts of the contract that are set. Guardian or admin only
+    Pause {
+        flags: PauseFlags,
+    },
+
+    /// takes over the contract after the owner proposed the sender with propose_owner
+    AcceptOwner {},
+
     /********** admin commands **********/
     /// global "claim" for all expired withdraws
     /// amount is the number of addresses we want to claim - this allows us to use "paging"
@@ -71,15 +139,23 @@ pub enum HandleMsg {
         weight: Option<u8>,
     },
 
-    /// add a new validator to the set
+    /// moves the whole stake of `from` to `to`, which has to be an active validator of the set
+    /// within the commission policy. `from` stays in the set
     Redelegate {
         from: String,
         to: String,
     },
-    /// Unbond everything
+
+    /// replaces the whole validator set. Validators that are left out keep their stake until it
+    /// is redelegated - a few redelegations at a time, continued with continue_validator_migration
+    ReplaceValidatorSet {
+        validators: Vec<ValidatorWeight>,
+    },
+    /// starts the emergency wind-down - freezes a snapshot and unbonds the first batch. The rest
+    /// is unbonded with continue_wind_down
     KillSwitchUnbond {},
 
-    /// open the floodgates
+    /// opens withdraws once the wind-down is done, without waiting for continue_wind_down
     KillSwitchOpenWithdraws {},
 
     ChangeUnbondingTime {
@@ -119,13 +195,88 @@ pub enum HandleMsg {
         denom: String,
         to: HumanAddr,
     },
-    ChangeOwner {
-        new_owner: HumanAddr,
+    /// the first step of an ownership transfer - the new owner has to accept_owner. None
+    /// withdraws the proposal
+    ProposeOwner {
+        new_owner: Option<HumanAddr>,
     },
     ChangeDevFee {
         dev_fee: Option<u64>,
         dev_address: Option<HumanAddr>,
     },
+    /// max_commission, drift_tolerance and max_validator_share are in the same units as the dev
+    /// fee. 10_000 = 10%
+    ChangeValidatorPolicy {
+        max_commission: Option<u64>,
+        drift_tolerance: Option<u64>,
+        strategy: Option<DelegationStrategyKind>,
+        max_validator_share: Option<u64>,
+        max_preferred_share: Option<u64>,
+    },
+    /// bounds for refresh_validator_set. Leaving out the oracle scores validators by commission
+    /// and slash record only
+    SetScoringConfig {
+        oracle: Option<Contract>,
+        min_weight: u8,
+        max_weight: u8,
+    },
+    /// share of the harvest paid to the caller of compound, in the same units as the dev fee
+    SetCompoundBounty {
+        bounty: u64,
+    },
+    /// the contract will be sent a RateUpdate (see RateSubscriberHandleMsg) on every compound
+    AddRateSubscriber {
+        contract: Contract,
+    },
+    RemoveRateSubscriber {
+        address: HumanAddr,
+    },
+    /// bounds on how fast the exchange rate may move before deposits and withdraws are halted,
+    /// in the same units as the dev fee
+    SetRateGuard {
+        max_change_per_window: Option<u64>,
+        max_change_per_block: Option<u64>,
+    },
+    /// roles can only be granted and revoked by the owner
+    GrantRole {
+        role: Role,
+        address: HumanAddr,
+    },
+    RevokeRole {
+        role: Role,
+        address: HumanAddr,
+    },
+    /// share of the deposit fee set aside to cover slashes, in the same units as the dev fee
+    SetInsuranceFeeShare {
+        fee_share: u64,
+    },
+    /// lifts the pauses that are set
+    Unpause {
+        flags: PauseFlags,
+    },
+    /// queues a timelocked command (recovering funds, changing the unbonding time, the voting
+    /// contract, minting, rate subscribers, where the dev fee goes or the delay itself). It can
+    /// be executed once the delay passed
+    ProposeAction {
+        msg: Box<HandleMsg>,
+    },
+    ExecuteAction {
+        id: u64,
+    },
+    /// the owner or a guardian can cancel a queued action
+    CancelAction {
+        id: u64,
+    },
+    /// in seconds
+    SetTimelockDelay {
+        delay: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ValidatorWeight {
+    pub address: String,
+    pub weight: u8,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -142,11 +293,78 @@ pub enum QueryMsg {
         current_time: u64,
     },
     Window {},
+    ValidatorPolicy {},
+    /// dry-run of the rebalance handle - shows the moves it would make right now
+    RebalancePlan {
+        max_moves: Option<u32>,
+        current_time: u64,
+    },
+    SlashHistory {},
+    /// every validator in the active set of the chain, best first
+    ValidatorScores {},
+    /// per-validator ledger of stake-time, harvested rewards, commission changes and slashes.
+    /// page starts from 0
+    ValidatorPerformance {
+        page: u32,
+        page_size: u32,
+    },
+    /// the retiring validators of a validator set replacement, and the next batch of moves
+    ValidatorMigration {
+        current_time: u64,
+    },
+    /// detailed view of the validator set, ordered by address
+    Validators {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// realised yield over the last `lookback_windows` withdraw windows, annualised
+    Apr {
+        lookback_windows: u32,
+    },
+    /// exchange rates recorded at every window advance and compound, oldest first. start_after
+    /// is a block time
+    ExchangeRateHistory {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// time-weighted average of the recorded exchange rates over the `period` seconds up to the
+    /// newest one
+    Twap {
+        period: u64,
+    },
+    RateSubscribers {},
+    RateGuard {},
+    /// what backs the tokens - assets and what we owe withdrawers. Claims of closed windows are
+    /// split into unbonding and matured at `current_time`
+    Backing {
+        current_time: u64,
+    },
+    /// cross-checks the withdraw windows, the claims, the delegations and the backing. Claims
+    /// are matured as of `current_time`
+    Solvency {
+        current_time: u64,
+    },
+    InsuranceFund {},
+    WindDown {},
+    PauseFlags {},
+    Roles {},
+    Timelock {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub enum MigrateMsg {}
+pub enum MigrateMsg {
+    /// state written by the first release
+    FromV1 {},
+}
+
+impl MigrateMsg {
+    pub fn from_version(&self) -> u32 {
+        match self {
+            MigrateMsg::FromV1 {} => 1,
+        }
+    }
+}
 
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
@@ -184,6 +402,109 @@ pub enum QueryResponse {
         id: u64,
         time_to_close: u64,
     },
+    ValidatorPolicy {
+        max_commission: u64,
+        drift_tolerance: u64,
+        strategy: DelegationStrategyKind,
+        max_validator_share: u64,
+        max_preferred_share: u64,
+    },
+    RebalancePlan {
+        moves: Vec<PlannedMove>,
+    },
+    SlashHistory {
+        events: Vec<SlashEvent>,
+        total: Uint128,
+    },
+    ValidatorScores {
+        scores: Vec<ValidatorScore>,
+    },
+    ValidatorPerformance {
+        validators: Vec<ValidatorPerformance>,
+    },
+    ValidatorMigration {
+        retiring: Vec<String>,
+        remaining: Uint128,
+        next_moves: Vec<PlannedMove>,
+    },
+    Validators {
+        validators: Vec<ValidatorDetails>,
+    },
+    /// apr is a fraction (0.1 = 10%), and is None until enough windows have closed. All amounts
+    /// are running totals in uscrt
+    Apr {
+        apr: Option<String>,
+        from: Option<RateSnapshot>,
+        to: Option<RateSnapshot>,
+        rewards_harvested: Uint128,
+        fees_taken: Uint128,
+        principal_deposited: Uint128,
+        principal_withdrawn: Uint128,
+    },
+    ExchangeRateHistory {
+        rates: Vec<ExchangeRatePoint>,
+    },
+    /// None until a rate was recorded. SCRT per token
+    Twap {
+        twap: Option<String>,
+        period: u64,
+    },
+    RateSubscribers {
+        contracts: Vec<Contract>,
+    },
+    RateGuard {
+        max_change_per_window: u64,
+        max_change_per_block: u64,
+        window_reference: Option<RateReference>,
+        last_seen: Option<RateReference>,
+        alert: Option<RateAlert>,
+        guardians: Vec<HumanAddr>,
+    },
+    /// all in uscrt. The exchange rate is priced off net_assets
+    Backing {
+        delegated: Uint128,
+        unbonding: Uint128,
+        liquid: Uint128,
+        rewards: Uint128,
+        pending_claims: Uint128,
+        unbonding_claims: Uint128,
+        matured_claims: Uint128,
+        idle: Uint128,
+        net_assets: Uint128,
+    },
+    /// every invariant that doesn't hold - empty when the contract is solvent
+    Solvency {
+        solvent: bool,
+        discrepancies: Vec<Discrepancy>,
+    },
+    /// the reserve is in uscrt. Payouts are the most recent ones, oldest first
+    InsuranceFund {
+        fee_share: u64,
+        reserve: Uint128,
+        total_paid_out: Uint128,
+        payouts: Vec<InsurancePayout>,
+    },
+    /// wind_down is None unless the kill switch was pulled. pool is what's left of the snapshot
+    /// to pay out, and payout_per_token is what a token is currently worth once withdraws are open
+    WindDown {
+        wind_down: Option<WindDown>,
+        pool: Uint128,
+        payout_per_token: Option<String>,
+    },
+    PauseFlags {
+        flags: PauseFlags,
+        guardians: Vec<HumanAddr>,
+    },
+    /// the owner can do everything the roles can
+    Roles {
+        owner: HumanAddr,
+        members: Vec<RoleMember>,
+    },
+    Timelock {
+        delay: u64,
+        queue: Vec<QueuedAction>,
+        pending_owner: Option<HumanAddr>,
+    },
     Info {
         token_address: HumanAddr,
         validators: Vec<ValidatorResponse>,
@@ -0,0 +1,61 @@
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, LogAttribute, Querier, StdError, StdResult, Storage,
+};
+
+use crate::types::config::read_config;
+use crate::types::pause::{read_pause_flags, set_pause_flags, PauseFlags};
+use crate::types::roles::{has_role, Role};
+
+/// Pauses every part of the contract that is set in `flags`. The guardian and the admin can
+/// pause - only the admin can unpause again
+pub fn try_pause<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    flags: PauseFlags,
+) -> StdResult<HandleResponse> {
+    let config = read_config(&deps.storage)?;
+    if env.message.sender != config.admin
+        && !has_role(&deps.storage, &env.message.sender, Role::Guardian)?
+    {
+        return Err(StdError::generic_err(
+            "Only the guardian or the admin can pause the contract",
+        ));
+    }
+
+    let mut paused = read_pause_flags(&deps.storage)?;
+    paused.pause(&flags);
+    set_pause_flags(&mut deps.storage, &paused)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: pause_logs("pause", &env, &paused),
+        data: None,
+    })
+}
+
+pub fn pause_logs(action: &str, env: &Env, paused: &PauseFlags) -> Vec<LogAttribute> {
+    vec![
+        log("action", action),
+        log("account", env.message.sender.as_str()),
+        log("deposits", paused.deposits),
+        log("withdraws", paused.withdraws),
+        log("claims", paused.claims),
+        log("validator_ops", paused.validator_ops),
+    ]
+}
+
+/// fails if `is_paused` says the operation is paused
+pub fn ensure_not_paused<S: Storage>(
+    storage: &S,
+    operation: &str,
+    is_paused: fn(&PauseFlags) -> bool,
+) -> StdResult<()> {
+    if is_paused(&read_pause_flags(storage)?) {
+        return Err(StdError::generic_err(format!(
+            "{} are paused at the moment",
+            operation
+        )));
+    }
+
+    Ok(())
+}
@@ -1,23 +1,44 @@
-use cosmwasm_std::{to_binary, Binary, HumanAddr, Querier, StdResult, Storage, Uint128};
-use rust_decimal::prelude::{One, Zero};
+use cosmwasm_std::{to_binary, Binary, HumanAddr, Querier, StdError, StdResult, Storage, Uint128};
+use rust_decimal::prelude::{FromStr, One, Zero};
 use rust_decimal::Decimal;
+use std::cmp::min;
+use std::convert::TryFrom;
 
+use crate::constants::{FEE_RESOLUTION, MAX_PAGE_SIZE, MAX_REDELEGATIONS_PER_TX, SECONDS_PER_YEAR};
 use crate::msg::{PendingClaimsResponse, QueryResponse};
-use crate::staking::{exchange_rate, get_total_onchain_balance};
+use crate::scoring::score_validators;
+use crate::solvency::audit_solvency;
+use crate::staking::{exchange_rate, get_backing, get_rewards_by_validator};
 use crate::state::get_address;
 use crate::types::activation_fee::read_activation_fee;
+use crate::types::claims_ledger::read_claims_ledger;
 use crate::types::config::read_config;
+use crate::types::delegation_strategy::DelegationStrategyKind;
+use crate::types::exchange_rate_history::{read_exchange_rate_history, time_weighted_rate};
+use crate::types::insurance::read_insurance_fund;
+use crate::types::pause::read_pause_flags;
 use crate::types::pending_withdraw::PendingWithdraw;
+use crate::types::rate_guard::read_rate_guard;
+use crate::types::rate_subscribers::read_rate_subscribers;
+use crate::types::roles::{read_roles, Role};
+use crate::types::slashing::read_slash_history;
+use crate::types::timelock::{read_pending_owner, read_timelock};
 use crate::types::user_withdraws::all_waiting_withdraws_for_user;
-use crate::types::validator_set::get_validator_set;
+use crate::types::validator_performance::read_performance_page;
+use crate::types::validator_policy::read_validator_policy;
+use crate::types::validator_set::{get_validator_set, ValidatorDetails};
+use crate::types::validator_status::ValidatorStatus;
+use crate::types::wind_down::read_wind_down;
 use crate::types::window_manager::get_window_manager;
 use crate::types::withdraw_window::get_claim_time;
+use crate::types::yield_ledger::{read_yield_ledger, RateSnapshot};
+use crate::wind_down::{remaining_pool, wind_down_payout};
 
 pub fn query_info<S: Storage, Q: Querier>(store: &S, querier: &Q) -> StdResult<Binary> {
     let config = read_config(store)?;
     let validator_set = get_validator_set(store)?;
     let contract_address = get_address(store)?;
-    let total_on_chain = get_total_onchain_balance(querier, store, &contract_address)?;
+    let total_on_chain = get_backing(querier, store, &contract_address, 0)?.net_assets();
 
     to_binary(&QueryResponse::Info {
         token_address: config.token_contract,
@@ -38,7 +59,7 @@ pub fn query_dev_fee<S: Storage>(store: &S) -> StdResult<Binary> {
 }
 
 pub fn query_exchange_rate<S: Storage, Q: Querier>(store: &S, querier: &Q) -> StdResult<Binary> {
-    let ratio = exchange_rate(store, querier)?;
+    let ratio = exchange_rate(store, querier, 0)?;
 
     let rate = if ratio.is_zero() {
         "1".to_string()
@@ -108,3 +129,330 @@ pub fn query_current_window<S: Storage>(store: &S) -> StdResult<Binary> {
         time_to_close: manager.time_to_close_window,
     })
 }
+
+pub fn query_validator_policy<S: Storage>(store: &S) -> StdResult<Binary> {
+    let policy = read_validator_policy(store)?;
+
+    to_binary(&QueryResponse::ValidatorPolicy {
+        max_commission: policy.max_commission,
+        drift_tolerance: policy.drift_tolerance,
+        strategy: DelegationStrategyKind::try_from(policy.strategy)?,
+        max_validator_share: policy.max_validator_share,
+        max_preferred_share: policy.max_preferred_share,
+    })
+}
+
+pub fn query_rebalance_plan<S: Storage>(
+    store: &S,
+    max_moves: Option<u32>,
+    current_time: u64,
+) -> StdResult<Binary> {
+    let policy = read_validator_policy(store)?;
+    let validator_set = get_validator_set(store)?;
+
+    let moves = validator_set.plan_rebalance(
+        min(
+            max_moves.unwrap_or(MAX_REDELEGATIONS_PER_TX),
+            MAX_REDELEGATIONS_PER_TX,
+        ),
+        policy.drift_tolerance,
+        policy.max_validator_share,
+        current_time,
+    );
+
+    to_binary(&QueryResponse::RebalancePlan { moves })
+}
+
+pub fn query_slash_history<S: Storage>(store: &S) -> StdResult<Binary> {
+    let history = read_slash_history(store)?;
+
+    to_binary(&QueryResponse::SlashHistory {
+        total: Uint128(history.total()),
+        events: history.events,
+    })
+}
+
+pub fn query_validator_scores<S: Storage, Q: Querier>(store: &S, querier: &Q) -> StdResult<Binary> {
+    to_binary(&QueryResponse::ValidatorScores {
+        scores: score_validators(store, querier)?,
+    })
+}
+
+pub fn query_validator_performance<S: Storage>(
+    store: &S,
+    page: u32,
+    page_size: u32,
+) -> StdResult<Binary> {
+    if page_size == 0 {
+        return Err(StdError::generic_err("page_size must be greater than 0"));
+    }
+
+    to_binary(&QueryResponse::ValidatorPerformance {
+        validators: read_performance_page(store, page, min(page_size, MAX_PAGE_SIZE))?,
+    })
+}
+
+pub fn query_validator_migration<S: Storage>(store: &S, current_time: u64) -> StdResult<Binary> {
+    let validator_set = get_validator_set(store)?;
+    let max_share = read_validator_policy(store)?.max_validator_share;
+    let retiring = validator_set.retiring();
+
+    to_binary(&QueryResponse::ValidatorMigration {
+        remaining: Uint128(retiring.iter().map(|v| v.staked).sum()),
+        retiring: retiring.into_iter().map(|v| v.address).collect(),
+        next_moves: validator_set.plan_migration(MAX_REDELEGATIONS_PER_TX, max_share, current_time),
+    })
+}
+
+pub fn query_validators<S: Storage, Q: Querier>(
+    store: &S,
+    querier: &Q,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let validator_set = get_validator_set(store)?;
+    let contract_address = get_address(store)?;
+    let limit = min(limit.unwrap_or(MAX_PAGE_SIZE), MAX_PAGE_SIZE) as usize;
+
+    let mut addresses: Vec<String> = validator_set
+        .addresses()
+        .into_iter()
+        .filter(|address| start_after.as_ref().map_or(true, |after| address > after))
+        .collect();
+    addresses.sort();
+    addresses.truncate(limit);
+
+    let chain_validators = querier.query_validators()?;
+    let rewards = get_rewards_by_validator(querier, &contract_address)?;
+    let targets = validator_set.targets(read_validator_policy(store)?.max_validator_share);
+    let total_staked = validator_set.total_staked();
+    let share = |amount: u128| -> u64 {
+        if total_staked == 0 {
+            0
+        } else {
+            (amount.saturating_mul(FEE_RESOLUTION) / total_staked) as u64
+        }
+    };
+
+    let mut validators: Vec<ValidatorDetails> = vec![];
+    for address in addresses {
+        let validator = match validator_set.get(&address) {
+            Some(validator) => validator,
+            None => continue,
+        };
+        let on_chain = chain_validators.iter().find(|v| v.address.0 == address);
+        let target = targets
+            .iter()
+            .find(|(a, _)| a == &address)
+            .map(|(_, target)| *target)
+            .unwrap_or_default();
+
+        validators.push(ValidatorDetails {
+            address: address.clone(),
+            weight: validator.weight,
+            status: ValidatorStatus::try_from(validator.status)?,
+            staked: Uint128(validator.staked),
+            target_share: share(target),
+            actual_share: share(validator.staked),
+            pending_rewards: Uint128(
+                rewards
+                    .iter()
+                    .find(|(a, _)| a == &address)
+                    .map(|(_, amount)| *amount)
+                    .unwrap_or_default(),
+            ),
+            unbonding: validator.unbonding.clone(),
+            on_chain_active: on_chain.is_some(),
+            commission: on_chain.map(|v| (Uint128(FEE_RESOLUTION) * v.commission).u128() as u64),
+            last_sync: validator.last_sync,
+        });
+    }
+
+    to_binary(&QueryResponse::Validators { validators })
+}
+
+pub fn query_apr<S: Storage>(store: &S, lookback_windows: u32) -> StdResult<Binary> {
+    if lookback_windows == 0 {
+        return Err(StdError::generic_err(
+            "lookback_windows must be greater than 0",
+        ));
+    }
+
+    let ledger = read_yield_ledger(store)?;
+    let snapshots = &ledger.snapshots;
+
+    let to = snapshots.last().cloned();
+    let from = snapshots
+        .len()
+        .checked_sub(1 + lookback_windows as usize)
+        .map(|i| snapshots[i].clone())
+        .or_else(|| snapshots.first().cloned());
+
+    let apr = match (&from, &to) {
+        (Some(from), Some(to)) if to.time > from.time => annualised_growth(from, to)?,
+        _ => None,
+    };
+
+    to_binary(&QueryResponse::Apr {
+        apr,
+        from,
+        to,
+        rewards_harvested: ledger.rewards_harvested,
+        fees_taken: ledger.fees_taken,
+        principal_deposited: ledger.principal_deposited,
+        principal_withdrawn: ledger.principal_withdrawn,
+    })
+}
+
+fn annualised_growth(from: &RateSnapshot, to: &RateSnapshot) -> StdResult<Option<String>> {
+    let parse = |rate: &str| {
+        Decimal::from_str(rate)
+            .map_err(|_| StdError::generic_err("Failed to parse exchange rate snapshot"))
+    };
+    let start = parse(&from.rate)?;
+    let end = parse(&to.rate)?;
+
+    if start.is_zero() {
+        return Ok(None);
+    }
+
+    let elapsed = Decimal::from(to.time - from.time);
+    let growth = end / start - Decimal::one();
+
+    Ok(Some(
+        (growth * Decimal::from(SECONDS_PER_YEAR) / elapsed)
+            .round_dp(6)
+            .to_string(),
+    ))
+}
+
+pub fn query_exchange_rate_history<S: Storage>(
+    store: &S,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = min(limit.unwrap_or(MAX_PAGE_SIZE), MAX_PAGE_SIZE);
+
+    to_binary(&QueryResponse::ExchangeRateHistory {
+        rates: read_exchange_rate_history(store, start_after, limit)?,
+    })
+}
+
+pub fn query_twap<S: Storage>(store: &S, period: u64) -> StdResult<Binary> {
+    to_binary(&QueryResponse::Twap {
+        twap: time_weighted_rate(store, period)?.map(|rate| rate.to_string()),
+        period,
+    })
+}
+
+pub fn query_rate_subscribers<S: Storage>(store: &S) -> StdResult<Binary> {
+    to_binary(&QueryResponse::RateSubscribers {
+        contracts: read_rate_subscribers(store)?.contracts,
+    })
+}
+
+pub fn query_rate_guard<S: Storage>(store: &S) -> StdResult<Binary> {
+    let guard = read_rate_guard(store)?;
+
+    to_binary(&QueryResponse::RateGuard {
+        max_change_per_window: guard.max_change_per_window,
+        max_change_per_block: guard.max_change_per_block,
+        window_reference: guard.window_reference,
+        last_seen: guard.last_seen,
+        alert: guard.alert,
+        guardians: read_roles(store)?.holders(Role::Guardian),
+    })
+}
+
+pub fn query_backing<S: Storage, Q: Querier>(
+    store: &S,
+    querier: &Q,
+    current_time: u64,
+) -> StdResult<Binary> {
+    let contract_address = get_address(store)?;
+    let backing = get_backing(querier, store, &contract_address, 0)?;
+    let (unbonding_claims, matured_claims) =
+        read_claims_ledger(store)?.outstanding_at(current_time);
+
+    to_binary(&QueryResponse::Backing {
+        delegated: Uint128(backing.delegated),
+        unbonding: Uint128(backing.unbonding),
+        liquid: Uint128(backing.liquid),
+        rewards: Uint128(backing.rewards),
+        pending_claims: Uint128(backing.pending_claims),
+        unbonding_claims: Uint128(unbonding_claims),
+        matured_claims: Uint128(matured_claims),
+        idle: Uint128(backing.idle()),
+        net_assets: Uint128(backing.net_assets()),
+    })
+}
+
+pub fn query_solvency<S: Storage, Q: Querier>(
+    store: &S,
+    querier: &Q,
+    current_time: u64,
+) -> StdResult<Binary> {
+    let discrepancies = audit_solvency(store, querier, current_time)?;
+
+    to_binary(&QueryResponse::Solvency {
+        solvent: discrepancies.is_empty(),
+        discrepancies,
+    })
+}
+
+pub fn query_insurance_fund<S: Storage>(store: &S) -> StdResult<Binary> {
+    let insurance = read_insurance_fund(store)?;
+
+    to_binary(&QueryResponse::InsuranceFund {
+        fee_share: insurance.fee_share,
+        reserve: insurance.reserve,
+        total_paid_out: insurance.total_paid_out,
+        payouts: insurance.payouts,
+    })
+}
+
+pub fn query_wind_down<S: Storage, Q: Querier>(store: &S, querier: &Q) -> StdResult<Binary> {
+    let wind_down = read_wind_down(store)?;
+    let contract_address = get_address(store)?;
+
+    let (pool, payout_per_token) = if let Some(wind_down) = &wind_down {
+        let (pool, _) = remaining_pool(store, querier, &contract_address, wind_down)?;
+        let payout = wind_down_payout(store, querier, &contract_address, Uint128(1_000_000))?;
+        (
+            pool,
+            Some((Decimal::from(payout as u64) / Decimal::from(1_000_000u64)).to_string()),
+        )
+    } else {
+        (0, None)
+    };
+
+    to_binary(&QueryResponse::WindDown {
+        wind_down,
+        pool: Uint128(pool),
+        payout_per_token,
+    })
+}
+
+pub fn query_pause_flags<S: Storage>(store: &S) -> StdResult<Binary> {
+    to_binary(&QueryResponse::PauseFlags {
+        flags: read_pause_flags(store)?,
+        guardians: read_roles(store)?.holders(Role::Guardian),
+    })
+}
+
+pub fn query_roles<S: Storage>(store: &S) -> StdResult<Binary> {
+    to_binary(&QueryResponse::Roles {
+        owner: read_config(store)?.admin,
+        members: read_roles(store)?.members,
+    })
+}
+
+pub fn query_timelock<S: Storage>(store: &S) -> StdResult<Binary> {
+    let timelock = read_timelock(store)?;
+
+    to_binary(&QueryResponse::Timelock {
+        delay: timelock.delay,
+        queue: timelock.queue,
+        pending_owner: read_pending_owner(store)?,
+    })
+}
@@ -0,0 +1,211 @@
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, LogAttribute, Querier, StdError, StdResult, Storage,
+};
+use rust_decimal::prelude::{FromStr, ToPrimitive};
+use rust_decimal::Decimal;
+
+use crate::constants::FEE_RESOLUTION;
+use crate::staking::{scrt_per_token, sent_scrt};
+use crate::types::config::read_config;
+use crate::types::exchange_rate_history::ExchangeRatePoint;
+use crate::types::rate_guard::{read_rate_guard, set_rate_guard, RateAlert, RateReference};
+use crate::types::roles::{has_role, Role};
+
+enum RateCheck {
+    Within,
+    Tripped(RateAlert),
+}
+
+/// Checks the current exchange rate against the bounds before a deposit or withdraw is priced,
+/// and fails the transaction if it moved too fast (see `rate_alert_error`). While an alert is
+/// pending, this fails outright
+pub fn ensure_exchange_rate<S: Storage, Q: Querier>(
+    storage: &mut S,
+    querier: &Q,
+    env: &Env,
+) -> StdResult<()> {
+    match check_exchange_rate(storage, querier, env)? {
+        RateCheck::Within => Ok(()),
+        RateCheck::Tripped(alert) => Err(rate_alert_error(&alert)),
+    }
+}
+
+/// A reverted deposit or withdraw can't store the alert, so the error carries it. Anyone can then
+/// store it with `raise_rate_alert`, which halts deposits and withdraws until it's overridden
+pub fn rate_alert_error(alert: &RateAlert) -> StdError {
+    StdError::generic_err(format!(
+        "exchange_rate_out_of_bounds: the exchange rate moved from {} to {} at height {}. Call raise_rate_alert to halt deposits and withdraws",
+        alert.reference, alert.rate, alert.height
+    ))
+}
+
+/// Checks the current exchange rate against the bounds. The rate is only recorded when it's
+/// within them - a tripped check leaves the guard as it was and returns the alert
+fn check_exchange_rate<S: Storage, Q: Querier>(
+    storage: &mut S,
+    querier: &Q,
+    env: &Env,
+) -> StdResult<RateCheck> {
+    let mut guard = read_rate_guard(storage)?;
+
+    if let Some(alert) = &guard.alert {
+        return Err(StdError::generic_err(format!(
+            "Deposits and withdraws are halted - the exchange rate moved from {} to {} at height {}",
+            alert.reference, alert.rate, alert.height
+        )));
+    }
+
+    let rate = scrt_per_token(storage, querier, sent_scrt(env))?;
+    let current = RateReference {
+        rate: rate.to_string(),
+        time: env.block.time,
+        height: env.block.height,
+    };
+
+    let mut tripped: Option<(String, u64)> = None;
+
+    if let Some(reference) = &guard.window_reference {
+        let change = relative_change(parse_rate(&reference.rate)?, rate);
+        if change > guard.max_change_per_window {
+            tripped = Some((reference.rate.clone(), change));
+        }
+    }
+
+    if let Some(last_seen) = &guard.last_seen {
+        let blocks = std::cmp::max(env.block.height.saturating_sub(last_seen.height), 1);
+        let change = relative_change(parse_rate(&last_seen.rate)?, rate);
+        if tripped.is_none() && change > guard.max_change_per_block.saturating_mul(blocks) {
+            tripped = Some((last_seen.rate.clone(), change));
+        }
+    }
+
+    if let Some((reference, change)) = tripped {
+        return Ok(RateCheck::Tripped(RateAlert {
+            rate: current.rate,
+            reference,
+            change,
+            time: env.block.time,
+            height: env.block.height,
+        }));
+    }
+
+    if guard.window_reference.is_none() {
+        guard.window_reference = Some(current.clone());
+    }
+    guard.last_seen = Some(current);
+    set_rate_guard(storage, &guard)?;
+
+    Ok(RateCheck::Within)
+}
+
+/// called as a window closes - the per-window bound is measured from here
+pub fn start_window_reference<S: Storage>(
+    storage: &mut S,
+    point: &ExchangeRatePoint,
+) -> StdResult<()> {
+    let mut guard = read_rate_guard(storage)?;
+    guard.window_reference = Some(RateReference {
+        rate: point.rate.clone(),
+        time: point.time,
+        height: point.height,
+    });
+    set_rate_guard(storage, &guard)
+}
+
+/// Stores the alert when the exchange rate is out of bounds, halting deposits and withdraws
+/// until the guardian or the admin overrides it. Anyone can call this - a deposit or withdraw
+/// that tripped the guard is reverted, and can't store the alert itself
+pub fn raise_rate_alert<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> StdResult<HandleResponse> {
+    let alert = match check_exchange_rate(&mut deps.storage, &deps.querier, &env)? {
+        RateCheck::Tripped(alert) => alert,
+        RateCheck::Within => {
+            return Err(StdError::generic_err(
+                "The exchange rate is within the bounds of the rate guard",
+            ));
+        }
+    };
+
+    let mut guard = read_rate_guard(&deps.storage)?;
+    guard.alert = Some(alert.clone());
+    set_rate_guard(&mut deps.storage, &guard)?;
+
+    let mut logs = vec![
+        log("action", "raise_rate_alert"),
+        log("account", env.message.sender.as_str()),
+    ];
+    logs.extend(alert_logs(&alert));
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: logs,
+        data: None,
+    })
+}
+
+fn alert_logs(alert: &RateAlert) -> Vec<LogAttribute> {
+    vec![
+        log("alert", "exchange_rate_out_of_bounds"),
+        log("rate", &alert.rate),
+        log("reference", &alert.reference),
+        log("change", alert.change),
+    ]
+}
+
+/// Accepts the current exchange rate after an alert was reviewed, and lets deposits and
+/// withdraws through again. Only the guardian and the admin can do this
+pub fn override_rate_guard<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> StdResult<HandleResponse> {
+    let config = read_config(&deps.storage)?;
+    if env.message.sender != config.admin
+        && !has_role(&deps.storage, &env.message.sender, Role::Guardian)?
+    {
+        return Err(StdError::generic_err(
+            "Only the guardian or the admin can override the rate guard",
+        ));
+    }
+
+    let rate = scrt_per_token(&deps.storage, &deps.querier, sent_scrt(&env))?;
+    let current = RateReference {
+        rate: rate.to_string(),
+        time: env.block.time,
+        height: env.block.height,
+    };
+
+    let mut guard = read_rate_guard(&deps.storage)?;
+    let cleared = guard.alert.take();
+    guard.window_reference = Some(current.clone());
+    guard.last_seen = Some(current);
+    set_rate_guard(&mut deps.storage, &guard)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "override_rate_guard"),
+            log("account", env.message.sender.as_str()),
+            log("rate", rate.to_string()),
+            log("cleared_alert", cleared.is_some()),
+        ],
+        data: None,
+    })
+}
+
+/// |to - from| / from, in FEE_RESOLUTION units
+fn relative_change(from: Decimal, to: Decimal) -> u64 {
+    if from.is_zero() {
+        return 0;
+    }
+
+    let diff = if to > from { to - from } else { from - to };
+    (diff / from * Decimal::from(FEE_RESOLUTION as u64))
+        .to_u64()
+        .unwrap_or(u64::MAX)
+}
+
+fn parse_rate(rate: &str) -> StdResult<Decimal> {
+    Decimal::from_str(rate).map_err(|_| StdError::generic_err("Failed to parse exchange rate"))
+}
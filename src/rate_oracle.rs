@@ -0,0 +1,81 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{to_binary, CosmosMsg, Env, Querier, StdResult, Storage, WasmMsg};
+
+use crate::staking::{scrt_per_token, sent_scrt};
+use crate::types::exchange_rate_history::{
+    append_exchange_rate, time_weighted_rate, ExchangeRatePoint,
+};
+use crate::types::rate_subscribers::read_rate_subscribers;
+
+/// the TWAP pushed to subscribers covers the last week
+pub const SUBSCRIBER_TWAP_PERIOD: u64 = 3600 * 24 * 7;
+
+/// The interface subscriber contracts have to implement. Rates are SCRT per token
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RateSubscriberHandleMsg {
+    RateUpdate {
+        rate: String,
+        twap: String,
+        twap_period: u64,
+        time: u64,
+        height: u64,
+    },
+}
+
+/// Records the current exchange rate in the history and returns the new point
+pub fn record_exchange_rate<S: Storage, Q: Querier>(
+    storage: &mut S,
+    querier: &Q,
+    env: &Env,
+) -> StdResult<ExchangeRatePoint> {
+    let rate = scrt_per_token(storage, querier, sent_scrt(env))?;
+    append_exchange_rate(storage, env.block.time, env.block.height, rate)
+}
+
+/// Records the current exchange rate, and returns a RateUpdate for every subscriber. Only
+/// compounds publish - a subscriber that fails to handle the update fails the whole transaction,
+/// so it must never be able to hold up withdraws. Subscribers that need the rate in between can
+/// query the Twap
+pub fn publish_exchange_rate<S: Storage, Q: Querier>(
+    storage: &mut S,
+    querier: &Q,
+    env: &Env,
+) -> StdResult<Vec<CosmosMsg>> {
+    let point = record_exchange_rate(storage, querier, env)?;
+    let rate = point.rate.clone();
+
+    let subscribers = read_rate_subscribers(storage)?;
+    if subscribers.contracts.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let twap = match time_weighted_rate(storage, SUBSCRIBER_TWAP_PERIOD)? {
+        Some(twap) => twap.to_string(),
+        None => rate.clone(),
+    };
+    let msg = to_binary(&RateSubscriberHandleMsg::RateUpdate {
+        rate,
+        twap,
+        twap_period: SUBSCRIBER_TWAP_PERIOD,
+        time: point.time,
+        height: point.height,
+    })?;
+
+    let messages = subscribers
+        .contracts
+        .into_iter()
+        .map(|contract| {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract.address,
+                callback_code_hash: contract.hash,
+                msg: msg.clone(),
+                send: vec![],
+            })
+        })
+        .collect();
+
+    Ok(messages)
+}
@@ -0,0 +1,471 @@
+use std::cmp::min;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{
+    log, to_binary, Api, Env, Extern, HandleResponse, LogAttribute, Querier, QueryRequest,
+    StdError, StdResult, Storage, Uint128, WasmQuery,
+};
+
+use crate::constants::FEE_RESOLUTION;
+use crate::pause::ensure_not_paused;
+use crate::types::config::read_config;
+use crate::types::scoring_config::{read_scoring_config, ScoringConfig};
+use crate::types::slashing::read_slash_history;
+use crate::types::validator_policy::read_validator_policy;
+use crate::types::validator_set::{get_validator_set, set_validator_set};
+use crate::validators::ensure_not_frozen;
+
+/// how much each component counts towards the final score. Sums up to 100
+const COMMISSION_WEIGHT: u128 = 30;
+const DECENTRALISATION_WEIGHT: u128 = 20;
+const UPTIME_WEIGHT: u128 = 20;
+const JAIL_WEIGHT: u128 = 15;
+const SLASH_WEIGHT: u128 = 15;
+
+/// validators that together hold this share of the voting power (counting from the largest) can
+/// halt the chain - we don't want to add to their stake. Same units as FEE_RESOLUTION
+const SUPERMINORITY_SHARE: u128 = 33_334;
+
+/// The interface the scoring oracle contract has to implement
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OracleQueryMsg {
+    ValidatorStats { validators: Vec<String> },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OracleQueryResponse {
+    ValidatorStats { stats: Vec<ValidatorStats> },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ValidatorStats {
+    pub address: String,
+    pub voting_power: Uint128,
+    /// share of recent blocks signed. Same units as FEE_RESOLUTION
+    pub uptime: u64,
+    pub times_jailed: u32,
+}
+
+/// All scores are in FEE_RESOLUTION units - 100_000 is a perfect score
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ValidatorScore {
+    pub address: String,
+    pub score: u64,
+    pub commission: u64,
+    pub decentralisation: u64,
+    pub uptime: u64,
+    pub jail: u64,
+    pub slash: u64,
+}
+
+/// Scores every validator in the active set of the chain, best first. When no oracle is
+/// configured, the components it would supply are neutral (full marks), so the ranking is decided
+/// by commission and slash record alone - jail history and voting power aren't penalised. When
+/// there is an oracle, a validator it has no stats for gets no marks for uptime and jail history
+pub fn score_validators<S: Storage, Q: Querier>(
+    storage: &S,
+    querier: &Q,
+) -> StdResult<Vec<ValidatorScore>> {
+    let policy = read_validator_policy(storage)?;
+    let scoring_config = read_scoring_config(storage)?;
+    let slashes = read_slash_history(storage)?;
+
+    let chain_validators = querier.query_validators()?;
+    let addresses: Vec<String> = chain_validators
+        .iter()
+        .map(|v| v.address.0.clone())
+        .collect();
+
+    let stats = query_oracle(querier, &scoring_config, addresses)?;
+    let superminority = superminority(&stats);
+    let unknown_stats_score = if scoring_config.oracle.is_some() {
+        0
+    } else {
+        FEE_RESOLUTION
+    };
+
+    let mut scores: Vec<ValidatorScore> = chain_validators
+        .iter()
+        .map(|validator| {
+            let address = validator.address.0.clone();
+            let validator_stats = stats.iter().find(|s| s.address == address);
+
+            let commission = (Uint128(FEE_RESOLUTION) * validator.commission).u128();
+            let commission_score = if commission > policy.max_commission as u128 {
+                0
+            } else if policy.max_commission == 0 {
+                FEE_RESOLUTION
+            } else {
+                FEE_RESOLUTION * (policy.max_commission as u128 - commission)
+                    / policy.max_commission as u128
+            };
+
+            let decentralisation_score = if superminority.contains(&address) {
+                0
+            } else {
+                FEE_RESOLUTION
+            };
+
+            let uptime_score = validator_stats
+                .map(|s| min(s.uptime as u128, FEE_RESOLUTION))
+                .unwrap_or(unknown_stats_score);
+
+            let jail_score = validator_stats
+                .map(|s| FEE_RESOLUTION / (1 + s.times_jailed as u128))
+                .unwrap_or(unknown_stats_score);
+
+            let times_slashed = slashes
+                .events
+                .iter()
+                .filter(|e| e.validator == address)
+                .count() as u128;
+            let slash_score = FEE_RESOLUTION / (1 + times_slashed);
+
+            let score = (commission_score * COMMISSION_WEIGHT
+                + decentralisation_score * DECENTRALISATION_WEIGHT
+                + uptime_score * UPTIME_WEIGHT
+                + jail_score * JAIL_WEIGHT
+                + slash_score * SLASH_WEIGHT)
+                / 100;
+
+            ValidatorScore {
+                address,
+                score: score as u64,
+                commission: commission_score as u64,
+                decentralisation: decentralisation_score as u64,
+                uptime: uptime_score as u64,
+                jail: jail_score as u64,
+                slash: slash_score as u64,
+            }
+        })
+        .collect();
+
+    scores.sort_by(|a, b| b.score.cmp(&a.score));
+
+    Ok(scores)
+}
+
+/// Maps a score linearly onto the admin-set weight bounds
+pub fn weight_for_score(config: &ScoringConfig, score: u64) -> u8 {
+    let range = config.max_weight.saturating_sub(config.min_weight) as u128;
+    let bonus = range * min(score as u128, FEE_RESOLUTION) / FEE_RESOLUTION;
+
+    config.min_weight + bonus as u8
+}
+
+/// Re-scores the validators in our set and adjusts their weights. Validators that dropped out of
+/// the active set of the chain get the minimum weight. Stake follows the new weights on the next
+/// rebalance. Needs an oracle - without one, jailed and oversized validators would score well
+pub fn refresh_validator_set<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> StdResult<HandleResponse> {
+    let config = read_config(&deps.storage)?;
+    ensure_not_frozen(&config)?;
+    ensure_not_paused(&deps.storage, "Validator operations", |paused| {
+        paused.validator_ops
+    })?;
+
+    let scoring_config = read_scoring_config(&deps.storage)?;
+    if scoring_config.oracle.is_none() {
+        return Err(StdError::generic_err(
+            "Validator weights can only be refreshed once a scoring oracle is set",
+        ));
+    }
+
+    let scores = score_validators(&deps.storage, &deps.querier)?;
+    let mut validator_set = get_validator_set(&deps.storage)?;
+
+    let mut logs: Vec<LogAttribute> = vec![
+        log("action", "refresh_validator_set"),
+        log("account", env.message.sender.as_str()),
+    ];
+
+    for address in validator_set.addresses() {
+        let weight = scores
+            .iter()
+            .find(|s| s.address == address)
+            .map(|s| weight_for_score(&scoring_config, s.score))
+            .unwrap_or(scoring_config.min_weight);
+
+        validator_set.change_weight(&address, Some(weight))?;
+        logs.push(log("validator", &address));
+        logs.push(log("weight", weight));
+    }
+
+    set_validator_set(&mut deps.storage, &validator_set)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: logs,
+        data: None,
+    })
+}
+
+fn query_oracle<Q: Querier>(
+    querier: &Q,
+    config: &ScoringConfig,
+    validators: Vec<String>,
+) -> StdResult<Vec<ValidatorStats>> {
+    let oracle = match &config.oracle {
+        Some(oracle) => oracle,
+        None => return Ok(vec![]),
+    };
+
+    let response: OracleQueryResponse = querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: oracle.address.clone(),
+        callback_code_hash: oracle.hash.clone(),
+        msg: to_binary(&OracleQueryMsg::ValidatorStats { validators })?,
+    }))?;
+
+    match response {
+        OracleQueryResponse::ValidatorStats { stats } => Ok(stats),
+    }
+}
+
+/// the smallest group of the largest validators that together hold at least a third of the
+/// voting power
+fn superminority(stats: &[ValidatorStats]) -> Vec<String> {
+    let total_power: u128 = stats.iter().map(|s| s.voting_power.u128()).sum();
+    if total_power == 0 {
+        return vec![];
+    }
+
+    let mut by_power: Vec<&ValidatorStats> = stats.iter().collect();
+    by_power.sort_by(|a, b| b.voting_power.cmp(&a.voting_power));
+
+    let threshold = total_power.saturating_mul(SUPERMINORITY_SHARE) / FEE_RESOLUTION;
+    let mut cumulative: u128 = 0;
+    let mut members: Vec<String> = vec![];
+
+    for validator in by_power {
+        if cumulative >= threshold {
+            break;
+        }
+        cumulative += validator.voting_power.u128();
+        members.push(validator.address.clone());
+    }
+
+    members
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cargo_common::contract::Contract;
+    use cosmwasm_std::testing::{mock_env, MockApi, MockStorage};
+    use cosmwasm_std::{
+        from_binary, from_slice, Decimal, Empty, HumanAddr, QuerierResult, StakingQuery, Validator,
+        ValidatorsResponse,
+    };
+
+    use crate::types::config::{set_config, Config};
+    use crate::types::scoring_config::set_scoring_config;
+    use crate::types::validator_set::ValidatorSet;
+
+    /// answers the validators query like the chain, and the stats query like the oracle
+    struct ScoringQuerier {
+        validators: Vec<Validator>,
+        stats: Vec<ValidatorStats>,
+    }
+
+    impl Querier for ScoringQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            let request: QueryRequest<Empty> = from_slice(bin_request).unwrap();
+            Ok(match request {
+                QueryRequest::Staking(StakingQuery::Validators {}) => {
+                    to_binary(&ValidatorsResponse {
+                        validators: self.validators.clone(),
+                    })
+                }
+                QueryRequest::Wasm(WasmQuery::Smart { msg, .. }) => {
+                    let OracleQueryMsg::ValidatorStats { validators } = from_binary(&msg).unwrap();
+                    to_binary(&OracleQueryResponse::ValidatorStats {
+                        stats: self
+                            .stats
+                            .iter()
+                            .filter(|s| validators.contains(&s.address))
+                            .cloned()
+                            .collect(),
+                    })
+                }
+                _ => Err(StdError::generic_err("unsupported query")),
+            })
+        }
+    }
+
+    fn chain_validator(address: &str, commission_percent: u64) -> Validator {
+        Validator {
+            address: HumanAddr(address.to_string()),
+            commission: Decimal::percent(commission_percent),
+            max_commission: Decimal::percent(20),
+            max_change_rate: Decimal::percent(1),
+        }
+    }
+
+    fn stats(address: &str, voting_power: u128, times_jailed: u32) -> ValidatorStats {
+        ValidatorStats {
+            address: address.to_string(),
+            voting_power: Uint128(voting_power),
+            uptime: FEE_RESOLUTION as u64,
+            times_jailed,
+        }
+    }
+
+    /// `big` holds almost all the voting power, the others differ only by commission
+    fn querier() -> ScoringQuerier {
+        ScoringQuerier {
+            validators: vec![
+                chain_validator("a", 1),
+                chain_validator("b", 5),
+                chain_validator("c", 9),
+                chain_validator("big", 0),
+            ],
+            stats: vec![
+                stats("a", 1, 0),
+                stats("b", 1, 0),
+                stats("c", 1, 0),
+                stats("big", 1_000_000, 0),
+            ],
+        }
+    }
+
+    fn scoring_config(oracle: bool) -> ScoringConfig {
+        ScoringConfig {
+            oracle: if oracle {
+                Some(Contract {
+                    address: HumanAddr("oracle".to_string()),
+                    hash: "hash".to_string(),
+                })
+            } else {
+                None
+            },
+            min_weight: 2,
+            max_weight: 10,
+        }
+    }
+
+    fn score_of(scores: &[ValidatorScore], address: &str) -> ValidatorScore {
+        scores
+            .iter()
+            .find(|s| s.address == address)
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn test_ranking() {
+        let mut storage = MockStorage::new();
+        set_scoring_config(&mut storage, &scoring_config(true)).unwrap();
+
+        let scores = score_validators(&storage, &querier()).unwrap();
+        let ranking: Vec<&str> = scores.iter().map(|s| s.address.as_str()).collect();
+
+        assert_eq!(ranking, vec!["a", "b", "big", "c"]);
+        assert_eq!(score_of(&scores, "a").commission, 90_000);
+        assert_eq!(score_of(&scores, "a").score, 97_000);
+    }
+
+    #[test]
+    fn test_voting_power_penalty() {
+        let mut storage = MockStorage::new();
+        set_scoring_config(&mut storage, &scoring_config(true)).unwrap();
+
+        let scores = score_validators(&storage, &querier()).unwrap();
+        let big = score_of(&scores, "big");
+
+        // the cheapest validator, but it alone can halt the chain
+        assert_eq!(big.commission, FEE_RESOLUTION as u64);
+        assert_eq!(big.decentralisation, 0);
+        assert!(big.score < score_of(&scores, "b").score);
+        assert_eq!(
+            score_of(&scores, "a").decentralisation,
+            FEE_RESOLUTION as u64
+        );
+    }
+
+    #[test]
+    fn test_jail_history_and_missing_stats() {
+        let mut storage = MockStorage::new();
+        set_scoring_config(&mut storage, &scoring_config(true)).unwrap();
+
+        let mut querier = querier();
+        querier.stats[1].times_jailed = 1;
+        querier.stats.remove(2);
+
+        let scores = score_validators(&storage, &querier).unwrap();
+        assert_eq!(score_of(&scores, "b").jail, 50_000);
+
+        // the oracle knows nothing about c, so it gets no benefit of the doubt
+        let c = score_of(&scores, "c");
+        assert_eq!((c.uptime, c.jail), (0, 0));
+
+        // without an oracle there is nothing to penalise
+        set_scoring_config(&mut storage, &scoring_config(false)).unwrap();
+        let c = score_of(&score_validators(&storage, &querier).unwrap(), "c");
+        assert_eq!(
+            (c.uptime, c.jail),
+            (FEE_RESOLUTION as u64, FEE_RESOLUTION as u64)
+        );
+    }
+
+    #[test]
+    fn test_weight_bounds() {
+        let config = scoring_config(true);
+
+        assert_eq!(weight_for_score(&config, 0), 2);
+        assert_eq!(weight_for_score(&config, 50_000), 6);
+        assert_eq!(weight_for_score(&config, FEE_RESOLUTION as u64), 10);
+        assert_eq!(weight_for_score(&config, u64::MAX), 10);
+    }
+
+    #[test]
+    fn test_refresh_validator_set() {
+        let mut deps = Extern {
+            storage: MockStorage::new(),
+            api: MockApi::new(20),
+            querier: querier(),
+        };
+        set_config(
+            &mut deps.storage,
+            &Config {
+                admin: HumanAddr("admin".to_string()),
+                token_contract: HumanAddr("token".to_string()),
+                token_contract_hash: "hash".to_string(),
+                gov_token: HumanAddr("gov".to_string()),
+                gov_token_hash: "hash".to_string(),
+                voting_admin: HumanAddr("admin".to_string()),
+                unbonding_time: 1_814_400,
+                kill_switch: 0,
+                dev_address: HumanAddr("dev".to_string()),
+                dev_fee: 1000,
+                shared_withdrawals: 0,
+            },
+        );
+
+        let mut validator_set = ValidatorSet::default();
+        for address in &["a", "big", "gone"] {
+            validator_set.add(address.to_string(), None);
+        }
+        set_validator_set(&mut deps.storage, &validator_set).unwrap();
+
+        // fails closed until there is an oracle
+        set_scoring_config(&mut deps.storage, &scoring_config(false)).unwrap();
+        assert!(refresh_validator_set(&mut deps, mock_env("anyone", &[])).is_err());
+
+        set_scoring_config(&mut deps.storage, &scoring_config(true)).unwrap();
+        refresh_validator_set(&mut deps, mock_env("anyone", &[])).unwrap();
+
+        let validator_set = get_validator_set(&deps.storage).unwrap();
+        let weight = |address: &str| validator_set.get(address).unwrap().weight;
+        assert_eq!(weight("a"), 9);
+        assert_eq!(weight("big"), 8);
+        // not in the active set anymore
+        assert_eq!(weight("gone"), 2);
+    }
+}
@@ -0,0 +1,363 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Querier, StdResult, Storage, Uint128};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+use crate::staking::{get_backing, get_delegations, scrt_per_token};
+use crate::state::get_address;
+use crate::tokens::query_total_supply;
+use crate::types::claims_ledger::read_claims_ledger;
+use crate::types::config::read_config;
+use crate::types::user_withdraws::{all_waiting_withdraws_for_user, UserWithdrawManager};
+use crate::types::validator_set::get_validator_set;
+use crate::types::window_manager::get_window_manager;
+
+/// the rate is rounded to 12 decimals, so supply * rate is only ever close to the backing
+pub const RATE_ROUNDING_TOLERANCE: u128 = 1_000;
+
+/// A broken invariant. `subject` is the window or the validator the check was about, if any
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Discrepancy {
+    pub check: String,
+    pub subject: Option<String>,
+    pub expected: Uint128,
+    pub actual: Uint128,
+}
+
+impl Discrepancy {
+    fn new(check: &str, subject: Option<String>, expected: u128, actual: u128) -> Self {
+        Self {
+            check: check.to_string(),
+            subject,
+            expected: Uint128(expected),
+            actual: Uint128(actual),
+        }
+    }
+}
+
+/// Cross-checks the internal bookkeeping against itself and against the chain. Returns every
+/// invariant that doesn't hold - an empty list means the contract is solvent
+pub fn audit_solvency<S: Storage, Q: Querier>(
+    store: &S,
+    querier: &Q,
+    current_time: u64,
+) -> StdResult<Vec<Discrepancy>> {
+    let mut discrepancies = vec![];
+
+    check_window_totals(store, &mut discrepancies)?;
+    check_claims_coverage(store, querier, current_time, &mut discrepancies)?;
+    check_delegations(store, querier, &mut discrepancies)?;
+    check_backing(store, querier, &mut discrepancies)?;
+
+    Ok(discrepancies)
+}
+
+/// the withdraws of every user in a window add up to what the window unbonded (or, for the open
+/// window, what it will unbond)
+fn check_window_totals<S: Storage>(
+    store: &S,
+    discrepancies: &mut Vec<Discrepancy>,
+) -> StdResult<()> {
+    let window_manager = get_window_manager(store)?;
+
+    let mut windows: Vec<(u64, u128)> = read_claims_ledger(store)?
+        .windows
+        .iter()
+        .map(|w| (w.window, w.outstanding.u128()))
+        .collect();
+    windows.push((
+        window_manager.current_active_window,
+        window_manager.window.coins.amount.u128(),
+    ));
+
+    for (window, recorded) in windows {
+        let mut owed = 0u128;
+        for user in UserWithdrawManager::new(window).users(store)? {
+            owed += all_waiting_withdraws_for_user(store, &user)
+                .0
+                .iter()
+                .filter(|w| w.id == window)
+                .map(|w| w.coins.amount.u128())
+                .sum::<u128>();
+        }
+
+        if owed != recorded {
+            discrepancies.push(Discrepancy::new(
+                "window_total",
+                Some(window.to_string()),
+                recorded,
+                owed,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// the claims that can already be made are paid out of the balance, so it has to cover them on
+/// its own. Claims and restake that are still unbonding haven't arrived yet - the restake that did
+/// arrive is already part of the balance
+fn check_claims_coverage<S: Storage, Q: Querier>(
+    store: &S,
+    querier: &Q,
+    current_time: u64,
+    discrepancies: &mut Vec<Discrepancy>,
+) -> StdResult<()> {
+    let contract_address = get_address(store)?;
+    let liquid = get_backing(querier, store, &contract_address, 0)?.liquid;
+    let (_, matured_claims) = read_claims_ledger(store)?.outstanding_at(current_time);
+
+    if liquid < matured_claims {
+        discrepancies.push(Discrepancy::new(
+            "matured_claims_coverage",
+            None,
+            matured_claims,
+            liquid,
+        ));
+    }
+
+    Ok(())
+}
+
+/// what we think is delegated, per validator and in total, is what the chain says is delegated
+fn check_delegations<S: Storage, Q: Querier>(
+    store: &S,
+    querier: &Q,
+    discrepancies: &mut Vec<Discrepancy>,
+) -> StdResult<()> {
+    let contract_address = get_address(store)?;
+    let validator_set = get_validator_set(store)?;
+    let delegations = get_delegations(querier, &contract_address)?;
+
+    for address in validator_set.addresses() {
+        let recorded = validator_set.get(&address).map_or(0, |v| v.staked);
+        let on_chain = delegations
+            .iter()
+            .find(|(validator, _)| validator == &address)
+            .map_or(0, |(_, amount)| *amount);
+
+        if recorded != on_chain {
+            discrepancies.push(Discrepancy::new(
+                "validator_delegation",
+                Some(address),
+                recorded,
+                on_chain,
+            ));
+        }
+    }
+
+    for (validator, amount) in delegations.iter() {
+        if validator_set.get(validator).is_none() && *amount > 0 {
+            discrepancies.push(Discrepancy::new(
+                "unknown_delegation",
+                Some(validator.clone()),
+                0,
+                *amount,
+            ));
+        }
+    }
+
+    let on_chain: u128 = delegations.iter().map(|(_, amount)| amount).sum();
+    if validator_set.total_staked() != on_chain {
+        discrepancies.push(Discrepancy::new(
+            "total_staked",
+            None,
+            validator_set.total_staked(),
+            on_chain,
+        ));
+    }
+
+    Ok(())
+}
+
+/// the tokens in circulation are worth what backs them. The rate is priced off our own record of
+/// the stake, so the backing it's checked against takes the delegations and the balance from the
+/// chain
+fn check_backing<S: Storage, Q: Querier>(
+    store: &S,
+    querier: &Q,
+    discrepancies: &mut Vec<Discrepancy>,
+) -> StdResult<()> {
+    let config = read_config(store)?;
+    let contract_address = get_address(store)?;
+
+    let supply =
+        query_total_supply(querier, &config.token_contract, &config.token_contract_hash)?.u128();
+    if supply == 0 {
+        return Ok(());
+    }
+
+    let mut backing = get_backing(querier, store, &contract_address, 0)?;
+    backing.delegated = get_delegations(querier, &contract_address)?
+        .iter()
+        .map(|(_, amount)| amount)
+        .sum();
+    let net_assets = backing.net_assets();
+    let rate = scrt_per_token(store, querier, 0)?;
+    let valued = (Decimal::from(supply as u64) * rate)
+        .to_u128()
+        .unwrap_or_default();
+
+    let diff = if valued > net_assets {
+        valued - net_assets
+    } else {
+        net_assets - valued
+    };
+    if diff > RATE_ROUNDING_TOLERANCE {
+        discrepancies.push(Discrepancy::new("supply_backing", None, net_assets, valued));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::HumanAddr;
+    use std::collections::VecDeque;
+
+    use crate::mock_querier::ChainQuerier;
+    use crate::state::store_address;
+    use crate::types::activation_fee::set_activation_fee;
+    use crate::types::claims_ledger::{set_claims_ledger, ClaimsLedger};
+    use crate::types::config::{set_config, Config};
+    use crate::types::pending_restake::{set_pending_restake, PendingRestake};
+    use crate::types::validator_set::{set_validator_set, Validator, ValidatorSet};
+    use crate::types::window_manager::{set_window_manager, WindowManager};
+
+    /// 1 SCRT staked on `a`
+    fn store_state<S: Storage>(storage: &mut S) {
+        store_address(storage, &HumanAddr("contract".to_string()));
+        set_config(
+            storage,
+            &Config {
+                admin: HumanAddr("admin".to_string()),
+                token_contract: HumanAddr("token".to_string()),
+                token_contract_hash: "hash".to_string(),
+                gov_token: HumanAddr("gov".to_string()),
+                gov_token_hash: "hash".to_string(),
+                voting_admin: HumanAddr("admin".to_string()),
+                unbonding_time: 1_814_400,
+                kill_switch: 0,
+                dev_address: HumanAddr("dev".to_string()),
+                dev_fee: 1000,
+                shared_withdrawals: 0,
+            },
+        );
+
+        set_activation_fee(storage, &0).unwrap();
+
+        let mut window_manager = WindowManager::default();
+        window_manager.window.coins.denom = "uscrt".to_string();
+        set_window_manager(storage, &window_manager).unwrap();
+
+        let validator = Validator {
+            address: "a".to_string(),
+            staked: 1_000_000,
+            weight: 10,
+            status: 0,
+            redelegation_cooldown: 0,
+            preferred: 0,
+            unbonding: vec![],
+            last_sync: 0,
+        };
+        set_validator_set(
+            storage,
+            &ValidatorSet::from_parts(VecDeque::from(vec![validator]), 0),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_matured_claims_are_covered_by_the_balance_alone() {
+        let mut storage = MockStorage::new();
+        store_state(&mut storage);
+
+        // window 0 can be claimed at 1_000, window 1 is still unbonding
+        let mut ledger = ClaimsLedger::default();
+        ledger.close_window(0, 100, 1_000);
+        ledger.close_window(1, 500, 5_000);
+        set_claims_ledger(&mut storage, &ledger).unwrap();
+
+        let mut restake = PendingRestake::default();
+        restake.append(1_000, 5_000);
+        set_pending_restake(&mut storage, &restake).unwrap();
+
+        let querier = ChainQuerier {
+            balance: 60,
+            delegations: vec![("a", 1_000_000)],
+            supply: 1_000_000,
+        };
+
+        let mut discrepancies = vec![];
+        check_claims_coverage(&storage, &querier, 2_000, &mut discrepancies).unwrap();
+        assert_eq!(
+            discrepancies,
+            vec![Discrepancy::new("matured_claims_coverage", None, 100, 60)]
+        );
+
+        let querier = ChainQuerier {
+            balance: 100,
+            ..querier
+        };
+        let mut discrepancies = vec![];
+        check_claims_coverage(&storage, &querier, 2_000, &mut discrepancies).unwrap();
+        assert!(discrepancies.is_empty());
+
+        // once window 1 matures too, the balance has to cover both
+        let mut discrepancies = vec![];
+        check_claims_coverage(&storage, &querier, 5_000, &mut discrepancies).unwrap();
+        assert_eq!(
+            discrepancies,
+            vec![Discrepancy::new("matured_claims_coverage", None, 600, 100)]
+        );
+    }
+
+    #[test]
+    fn test_backing_is_checked_against_the_chain() {
+        let mut storage = MockStorage::new();
+        store_state(&mut storage);
+
+        let querier = ChainQuerier {
+            balance: 0,
+            delegations: vec![("a", 1_000_000)],
+            supply: 1_000_000,
+        };
+        let mut discrepancies = vec![];
+        check_backing(&storage, &querier, &mut discrepancies).unwrap();
+        assert!(discrepancies.is_empty());
+
+        // slashed, and we haven't noticed yet - the rate still prices in the stake on record
+        let querier = ChainQuerier {
+            delegations: vec![("a", 900_000)],
+            ..querier
+        };
+        let mut discrepancies = vec![];
+        check_backing(&storage, &querier, &mut discrepancies).unwrap();
+        assert_eq!(
+            discrepancies,
+            vec![Discrepancy::new("supply_backing", None, 900_000, 1_000_000)]
+        );
+
+        // the balance the chain reports counts towards the backing
+        let querier = ChainQuerier {
+            balance: 1_000_000,
+            ..querier
+        };
+        let mut discrepancies = vec![];
+        check_backing(&storage, &querier, &mut discrepancies).unwrap();
+        assert_eq!(
+            discrepancies,
+            vec![Discrepancy::new(
+                "supply_backing",
+                None,
+                1_900_000,
+                2_000_000
+            )]
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use std::convert::TryFrom;
 
 use cosmwasm_std::{
-    debug_print, Coin, CosmosMsg, DistQuery, HumanAddr, Querier, RewardsResponse, StakingMsg,
+    debug_print, Coin, CosmosMsg, DistQuery, Env, HumanAddr, Querier, RewardsResponse, StakingMsg,
     StdError, StdResult, Storage, Uint128,
 };
 use rust_decimal::prelude::*;
@@ -9,17 +9,27 @@ use rust_decimal::Decimal;
 
 use crate::state::{get_address, get_frozen_exchange_rate};
 use crate::tokens::query_total_supply;
+use crate::types::activation_fee::read_activation_fee;
+use crate::types::claims_ledger::read_claims_ledger;
 use crate::types::config::read_config;
+use crate::types::insurance::read_insurance_fund;
 use crate::types::killswitch::KillSwitch;
+use crate::types::pending_restake::read_pending_restake;
 use crate::types::validator_set::get_validator_set;
+use crate::types::window_manager::get_window_manager;
 
-pub fn exchange_rate<S: Storage, Q: Querier>(store: &S, querier: &Q) -> StdResult<Decimal> {
+/// tokens per SCRT of net assets. See get_backing for `incoming`
+pub fn exchange_rate<S: Storage, Q: Querier>(
+    store: &S,
+    querier: &Q,
+    incoming: u128,
+) -> StdResult<Decimal> {
     let contract_address = get_address(store)?;
 
     let config = read_config(store)?;
 
     if KillSwitch::try_from(config.kill_switch)? == KillSwitch::Closed {
-        let total_on_chain = get_total_onchain_balance(querier, store, &contract_address)?;
+        let total_on_chain = get_backing(querier, store, &contract_address, incoming)?.net_assets();
         let tokens =
             query_total_supply(querier, &config.token_contract, &config.token_contract_hash)?
                 .u128();
@@ -45,6 +55,21 @@ pub fn exchange_rate<S: Storage, Q: Querier>(store: &S, querier: &Q) -> StdResul
     }
 }
 
+/// the inverse of exchange_rate - how much SCRT a single token is worth
+pub fn scrt_per_token<S: Storage, Q: Querier>(
+    store: &S,
+    querier: &Q,
+    incoming: u128,
+) -> StdResult<Decimal> {
+    let ratio = exchange_rate(store, querier, incoming)?;
+
+    if ratio.is_zero() {
+        Ok(Decimal::one())
+    } else {
+        Ok(Decimal::one() / ratio)
+    }
+}
+
 fn _calc_exchange_rate(total_on_chain: u128, tokens: u128) -> Result<Decimal, StdError> {
     let scrt_balance = Decimal::from(total_on_chain as u64);
     let token_bal = Decimal::from(tokens as u64);
@@ -68,19 +93,71 @@ pub fn get_locked_balance<Q: Querier>(
     Ok(staked_balance.u128())
 }
 
-pub fn get_total_onchain_balance<Q: Querier, S: Storage>(
+/// What backs the tokens. The SCRT that's unbonding for closed windows is netted against the
+/// claims on it, so it shows up on both sides
+#[derive(Debug, Clone, Default)]
+pub struct Backing {
+    pub delegated: u128,
+    /// undelegations we get back - stake on its way to be restaked
+    pub unbonding: u128,
+    /// the contract balance, minus what was sent with the current message, the activation fee
+    /// and the insurance reserve
+    pub liquid: u128,
+    pub rewards: u128,
+    /// withdraws in the open window - the tokens are burned but the SCRT is still delegated
+    pub pending_claims: u128,
+    /// unclaimed withdraws of closed windows, unbonding or matured
+    pub claims: u128,
+}
+
+impl Backing {
+    /// Liquid SCRT only counts once it covers every claim and every restake, since we can't
+    /// tell which of the unbondings already arrived
+    pub fn idle(&self) -> u128 {
+        self.liquid.saturating_sub(self.claims + self.unbonding)
+    }
+
+    pub fn net_assets(&self) -> u128 {
+        (self.delegated + self.unbonding + self.rewards + self.idle())
+            .saturating_sub(self.pending_claims)
+    }
+}
+
+/// `incoming` is the SCRT sent with the current message - it's already in our balance, but it
+/// doesn't back any tokens yet
+pub fn get_backing<Q: Querier, S: Storage>(
     querier: &Q,
     storage: &S,
     contract_address: &HumanAddr,
-) -> StdResult<u128> {
+    incoming: u128,
+) -> StdResult<Backing> {
     let validator_set = get_validator_set(storage)?;
-    let locked_balance = validator_set.total_staked();
-
-    let rewards_balance = get_rewards(querier, contract_address)
-        .unwrap_or_default()
-        .u128();
+    let window_manager = get_window_manager(storage)?;
+
+    let liquid = get_balance(querier, contract_address)?
+        .u128()
+        .saturating_sub(incoming)
+        .saturating_sub(read_activation_fee(storage)? as u128)
+        .saturating_sub(read_insurance_fund(storage)?.reserve.u128());
+
+    Ok(Backing {
+        delegated: validator_set.total_staked(),
+        unbonding: read_pending_restake(storage)?.total(),
+        liquid,
+        rewards: get_rewards(querier, contract_address)?.u128(),
+        pending_claims: window_manager.window.coins.amount.u128(),
+        claims: read_claims_ledger(storage)?.total_outstanding(),
+    })
+}
 
-    Ok(locked_balance + rewards_balance)
+/// the uscrt sent along with the message
+pub fn sent_scrt(env: &Env) -> u128 {
+    env.message
+        .sent_funds
+        .iter()
+        .filter(|coin| coin.denom == "uscrt")
+        .map(|coin| coin.amount.u128())
+        .sum()
 }
 
 pub fn get_balance<Q: Querier>(querier: &Q, address: &HumanAddr) -> StdResult<Uint128> {
@@ -94,13 +171,7 @@ pub fn get_rewards<Q: Querier>(querier: &Q, contract: &HumanAddr) -> StdResult<U
         delegator: contract.clone(),
     };
 
-    let query_rewards: RewardsResponse =
-        querier
-            .query(&query.into())
-            .unwrap_or_else(|_| RewardsResponse {
-                rewards: vec![],
-                total: vec![],
-            });
+    let query_rewards: RewardsResponse = querier.query(&query.into())?;
 
     if query_rewards.total.is_empty() {
         return Ok(Uint128(0));
@@ -119,6 +190,32 @@ pub fn get_rewards<Q: Querier>(querier: &Q, contract: &HumanAddr) -> StdResult<U
     })
 }
 
+/// pending rewards of the contract, per validator
+pub fn get_rewards_by_validator<Q: Querier>(
+    querier: &Q,
+    contract: &HumanAddr,
+) -> StdResult<Vec<(String, u128)>> {
+    let query = DistQuery::Rewards {
+        delegator: contract.clone(),
+    };
+
+    let query_rewards: RewardsResponse = querier.query(&query.into())?;
+
+    Ok(query_rewards
+        .rewards
+        .into_iter()
+        .map(|r| {
+            let amount: u128 = r
+                .reward
+                .iter()
+                .filter(|c| c.denom == "uscrt")
+                .map(|c| c.amount.u128())
+                .sum();
+            (r.validator_address.0, amount)
+        })
+        .collect())
+}
+
 pub fn get_rewards_limited<Q: Querier>(
     querier: &Q,
     contract: &HumanAddr,
@@ -128,13 +225,7 @@ pub fn get_rewards_limited<Q: Querier>(
         delegator: contract.clone(),
     };
 
-    let mut query_rewards: RewardsResponse =
-        querier
-            .query(&query.into())
-            .unwrap_or_else(|_| RewardsResponse {
-                rewards: vec![],
-                total: vec![],
-            });
+    let mut query_rewards: RewardsResponse = querier.query(&query.into())?;
 
     if query_rewards.rewards.len() < amount as usize {
         return Ok(query_rewards);
@@ -221,6 +312,27 @@ pub fn get_bonded<Q: Querier>(querier: &Q, contract: &HumanAddr) -> StdResult<Ui
     })
 }
 
+// get_delegations returns how much the contract really has delegated to each validator
+pub fn get_delegations<Q: Querier>(
+    querier: &Q,
+    contract: &HumanAddr,
+) -> StdResult<Vec<(String, u128)>> {
+    let bonds = querier.query_all_delegations(contract)?;
+
+    bonds
+        .into_iter()
+        .map(|d| {
+            if d.amount.denom != "uscrt" {
+                return Err(StdError::generic_err(format!(
+                    "unexpected denom in bonds: '{}'",
+                    &d.amount.denom
+                )));
+            }
+            Ok((d.validator.0, d.amount.amount.u128()))
+        })
+        .collect()
+}
+
 // get_bonded returns the total amount of delegations from contract
 // it ensures they are all the same denom
 // Simon I'm trusting you that this works don't let me down bro
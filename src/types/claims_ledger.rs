@@ -0,0 +1,76 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdResult, Storage, Uint128};
+use cosmwasm_storage::{ReadonlySingleton, Singleton};
+
+pub static KEY_CLAIMS_LEDGER: &[u8] = b"claims_ledger";
+
+/// What we still owe the users of a closed window
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct WindowClaims {
+    pub window: u64,
+    pub claim_time: u64,
+    /// unbonded for the window, in uscrt
+    pub amount: Uint128,
+    /// not claimed yet, in uscrt
+    pub outstanding: Uint128,
+}
+
+/// Closed windows that still have unclaimed withdraws. A window is dropped once it's fully claimed
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, Default)]
+pub struct ClaimsLedger {
+    #[serde(default)]
+    pub windows: Vec<WindowClaims>,
+}
+
+impl ClaimsLedger {
+    pub fn close_window(&mut self, window: u64, amount: u128, claim_time: u64) {
+        if amount == 0 {
+            return;
+        }
+
+        self.windows.push(WindowClaims {
+            window,
+            claim_time,
+            amount: Uint128(amount),
+            outstanding: Uint128(amount),
+        });
+    }
+
+    pub fn claim(&mut self, window: u64, amount: u128) {
+        if let Some(pos) = self.windows.iter().position(|w| w.window == window) {
+            let outstanding = self.windows[pos].outstanding.u128().saturating_sub(amount);
+            if outstanding == 0 {
+                self.windows.remove(pos);
+            } else {
+                self.windows[pos].outstanding = Uint128(outstanding);
+            }
+        }
+    }
+
+    pub fn total_outstanding(&self) -> u128 {
+        self.windows.iter().map(|w| w.outstanding.u128()).sum()
+    }
+
+    /// outstanding claims split into (still unbonding, matured) at `current_time`
+    pub fn outstanding_at(&self, current_time: u64) -> (u128, u128) {
+        self.windows.iter().fold((0, 0), |(unbonding, matured), w| {
+            if w.claim_time > current_time {
+                (unbonding + w.outstanding.u128(), matured)
+            } else {
+                (unbonding, matured + w.outstanding.u128())
+            }
+        })
+    }
+}
+
+pub fn set_claims_ledger<S: Storage>(storage: &mut S, data: &ClaimsLedger) -> StdResult<()> {
+    Singleton::new(storage, KEY_CLAIMS_LEDGER).save(data)
+}
+
+pub fn read_claims_ledger<S: Storage>(storage: &S) -> StdResult<ClaimsLedger> {
+    Ok(ReadonlySingleton::new(storage, KEY_CLAIMS_LEDGER)
+        .may_load()?
+        .unwrap_or_default())
+}
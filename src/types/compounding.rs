@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdResult, Storage};
+use cosmwasm_storage::{ReadonlySingleton, Singleton};
+
+pub static KEY_COMPOUNDING: &[u8] = b"compounding";
+
+/// 1%, in the same units as the dev fee
+pub const DEFAULT_COMPOUND_BOUNTY: u64 = 1_000;
+/// 10%
+pub const MAX_COMPOUND_BOUNTY: u64 = 10_000;
+
+/// State of the permissionless compound handle. The cursor is the last validator that was
+/// harvested - the next call continues after it, and wraps around once the end of the set is
+/// reached
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq)]
+pub struct Compounding {
+    /// share of the harvested rewards paid to whoever calls compound
+    #[serde(default = "default_bounty")]
+    pub bounty: u64,
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+impl Default for Compounding {
+    fn default() -> Self {
+        Self {
+            bounty: DEFAULT_COMPOUND_BOUNTY,
+            cursor: None,
+        }
+    }
+}
+
+fn default_bounty() -> u64 {
+    DEFAULT_COMPOUND_BOUNTY
+}
+
+pub fn set_compounding<S: Storage>(storage: &mut S, data: &Compounding) -> StdResult<()> {
+    Singleton::new(storage, KEY_COMPOUNDING).save(data)
+}
+
+pub fn read_compounding<S: Storage>(storage: &S) -> StdResult<Compounding> {
+    Ok(ReadonlySingleton::new(storage, KEY_COMPOUNDING)
+        .may_load()?
+        .unwrap_or_default())
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{HumanAddr, ReadonlyStorage, StdResult, Storage};
+use cosmwasm_std::{HumanAddr, ReadonlyStorage, StdError, StdResult, Storage};
 use cosmwasm_storage::{PrefixedStorage, ReadonlyPrefixedStorage};
 
 pub static PREFIX_CONFIG: &[u8] = b"config";
@@ -30,9 +30,13 @@ pub fn set_config<S: Storage>(storage: &mut S, config: &Config) {
 
 pub fn read_config<S: Storage>(store: &S) -> StdResult<Config> {
     let config_store = ReadonlyPrefixedStorage::new(PREFIX_CONFIG, store);
-    let consts_bytes = config_store.get(CONFIG_KEY).unwrap();
+    let consts_bytes = config_store
+        .get(CONFIG_KEY)
+        .ok_or_else(|| StdError::generic_err("There is no stored config"))?;
 
-    let consts: Config = bincode2::deserialize(&consts_bytes).unwrap();
+    let consts: Config = bincode2::deserialize(&consts_bytes).map_err(|_| {
+        StdError::generic_err("Error unpacking config - it may need to be migrated")
+    })?;
 
     Ok(consts)
 }
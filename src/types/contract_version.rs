@@ -0,0 +1,22 @@
+use cosmwasm_std::{StdResult, Storage};
+use cosmwasm_storage::{ReadonlySingleton, Singleton};
+
+pub static KEY_CONTRACT_VERSION: &[u8] = b"contract_version";
+
+/// The layout of the state this code reads. Bump it whenever anything stored as bincode changes
+/// shape, and add a MigrateMsg variant that upgrades the previous layout. That's the config, the
+/// window manager, the validator set and the contract address, the active withdraw window and the
+/// claim times, the CashMap entries (user withdraws, validator performance) and the AppendStore
+/// entries (the users of each window, the exchange rate history). bincode isn't self-describing,
+/// so an entry in an older layout either fails to load or, worse, loads as something else. The
+/// JSON singletons don't need a bump as long as new fields have defaults
+pub const CONTRACT_VERSION: u32 = 2;
+
+pub fn set_contract_version<S: Storage>(storage: &mut S, version: u32) -> StdResult<()> {
+    Singleton::new(storage, KEY_CONTRACT_VERSION).save(&version)
+}
+
+/// None for contracts that were deployed before the version was stored
+pub fn read_contract_version<S: Storage>(storage: &S) -> StdResult<Option<u32>> {
+    ReadonlySingleton::new(storage, KEY_CONTRACT_VERSION).may_load()
+}
@@ -0,0 +1,512 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdError, StdResult};
+use std::cmp::{max, min};
+use std::convert::TryFrom;
+
+use crate::constants::FEE_RESOLUTION;
+use crate::types::validator_policy::ValidatorPolicy;
+use crate::types::validator_set::Validator;
+
+/// (validator address, amount) pairs
+pub type Allocations = Vec<(String, u128)>;
+
+/// Decides where new stake is placed, and where unbonds are taken from. Strategies only ever see
+/// healthy validators, and never have to worry about storage - `ValidatorSet` applies the result
+pub trait DelegationStrategy {
+    /// splits `amount` of new stake between `validators`. `cursor` increases by one every time
+    /// stake is placed, for strategies that need to remember where they stopped
+    fn allocate_stake(
+        &self,
+        validators: &[Validator],
+        amount: u128,
+        cursor: u64,
+    ) -> StdResult<Allocations>;
+
+    /// picks where `amount` is unbonded from. May return less than `amount` if the validators
+    /// don't have enough stake
+    fn allocate_unbond(
+        &self,
+        validators: &[Validator],
+        amount: u128,
+        cursor: u64,
+    ) -> StdResult<Allocations>;
+}
+
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DelegationStrategyKind {
+    WeightedLowestStake,
+    RoundRobin,
+    Proportional,
+    ConcentrationCapped,
+}
+
+impl TryFrom<u8> for DelegationStrategyKind {
+    type Error = StdError;
+
+    fn try_from(other: u8) -> Result<Self, Self::Error> {
+        match other {
+            0 => Ok(Self::WeightedLowestStake),
+            1 => Ok(Self::RoundRobin),
+            2 => Ok(Self::Proportional),
+            3 => Ok(Self::ConcentrationCapped),
+            _ => Err(StdError::generic_err(
+                "Failed to convert DelegationStrategyKind enum",
+            )),
+        }
+    }
+}
+
+impl Into<u8> for DelegationStrategyKind {
+    fn into(self) -> u8 {
+        match self {
+            Self::WeightedLowestStake => 0u8,
+            Self::RoundRobin => 1u8,
+            Self::Proportional => 2u8,
+            Self::ConcentrationCapped => 3u8,
+        }
+    }
+}
+
+pub fn strategy_from_policy(policy: &ValidatorPolicy) -> StdResult<Box<dyn DelegationStrategy>> {
+    Ok(match DelegationStrategyKind::try_from(policy.strategy)? {
+        DelegationStrategyKind::WeightedLowestStake => Box::new(WeightedLowestStake),
+        DelegationStrategyKind::RoundRobin => Box::new(RoundRobin),
+        DelegationStrategyKind::Proportional => Box::new(Proportional),
+        DelegationStrategyKind::ConcentrationCapped => Box::new(ConcentrationCapped {
+            max_share: policy.max_validator_share,
+        }),
+    })
+}
+
+/// Stake goes to the validator with the lowest stake relative to its weight. Unbonds come from
+/// the validator with the highest stake relative to its weight, at most half of its stake at a
+/// time before re-evaluating
+pub struct WeightedLowestStake;
+
+impl DelegationStrategy for WeightedLowestStake {
+    fn allocate_stake(
+        &self,
+        validators: &[Validator],
+        amount: u128,
+        _cursor: u64,
+    ) -> StdResult<Allocations> {
+        let validator = validators.iter().min().ok_or_else(|| {
+            StdError::generic_err("Failed to get validator to stake - no active validators")
+        })?;
+
+        Ok(vec![(validator.address.clone(), amount)])
+    }
+
+    fn allocate_unbond(
+        &self,
+        validators: &[Validator],
+        amount: u128,
+        _cursor: u64,
+    ) -> StdResult<Allocations> {
+        let mut remaining_validators = validators.to_vec();
+        let mut allocations: Allocations = vec![];
+        let mut remaining = amount;
+
+        while remaining > 0 {
+            let validator = match remaining_validators
+                .iter_mut()
+                .filter(|v| v.staked > 0)
+                .max()
+            {
+                Some(validator) => validator,
+                None => break,
+            };
+
+            // don't unbond more than 50% of the a validator's stake and then re-evaluate
+            let to_unbond = min(max(validator.staked / 2, 1), remaining);
+            validator.staked -= to_unbond;
+            remaining -= to_unbond;
+
+            add_allocation(&mut allocations, &validator.address, to_unbond);
+        }
+
+        Ok(allocations)
+    }
+}
+
+/// Every deposit goes to the next validator in turn (ordered by address). Unbonds walk the same
+/// order, starting from the current position
+pub struct RoundRobin;
+
+impl RoundRobin {
+    fn ordered(validators: &[Validator]) -> Vec<Validator> {
+        let mut ordered = validators.to_vec();
+        ordered.sort_by(|a, b| a.address.cmp(&b.address));
+        ordered
+    }
+}
+
+impl DelegationStrategy for RoundRobin {
+    fn allocate_stake(
+        &self,
+        validators: &[Validator],
+        amount: u128,
+        cursor: u64,
+    ) -> StdResult<Allocations> {
+        if validators.is_empty() {
+            return Err(StdError::generic_err(
+                "Failed to get validator to stake - no active validators",
+            ));
+        }
+
+        let ordered = Self::ordered(validators);
+        let validator = &ordered[(cursor % ordered.len() as u64) as usize];
+
+        Ok(vec![(validator.address.clone(), amount)])
+    }
+
+    fn allocate_unbond(
+        &self,
+        validators: &[Validator],
+        amount: u128,
+        cursor: u64,
+    ) -> StdResult<Allocations> {
+        let ordered = Self::ordered(validators);
+        if ordered.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let start = (cursor % ordered.len() as u64) as usize;
+        let mut allocations: Allocations = vec![];
+        let mut remaining = amount;
+
+        for i in 0..ordered.len() {
+            if remaining == 0 {
+                break;
+            }
+            let validator = &ordered[(start + i) % ordered.len()];
+            let to_unbond = min(validator.staked, remaining);
+            if to_unbond > 0 {
+                add_allocation(&mut allocations, &validator.address, to_unbond);
+                remaining -= to_unbond;
+            }
+        }
+
+        Ok(allocations)
+    }
+}
+
+/// Deposits are split between all validators according to weight. Unbonds are split according to
+/// how much each validator currently has staked
+pub struct Proportional;
+
+impl DelegationStrategy for Proportional {
+    fn allocate_stake(
+        &self,
+        validators: &[Validator],
+        amount: u128,
+        _cursor: u64,
+    ) -> StdResult<Allocations> {
+        let shares: Vec<(String, u128)> = validators
+            .iter()
+            .map(|v| (v.address.clone(), v.weight as u128))
+            .collect();
+
+        split_by_shares(&shares, amount).ok_or_else(|| {
+            StdError::generic_err("Failed to get validator to stake - no active validators")
+        })
+    }
+
+    fn allocate_unbond(
+        &self,
+        validators: &[Validator],
+        amount: u128,
+        _cursor: u64,
+    ) -> StdResult<Allocations> {
+        let total: u128 = validators.iter().map(|v| v.staked).sum();
+        let shares: Vec<(String, u128)> = validators
+            .iter()
+            .map(|v| (v.address.clone(), v.staked))
+            .collect();
+
+        Ok(split_by_shares(&shares, min(amount, total)).unwrap_or_default())
+    }
+}
+
+/// Like weighted lowest-stake-first, but a single deposit never pushes a validator above
+/// `max_share` of the total stake - the rest spills over to the next validators in line
+pub struct ConcentrationCapped {
+    pub max_share: u64,
+}
+
+impl DelegationStrategy for ConcentrationCapped {
+    fn allocate_stake(
+        &self,
+        validators: &[Validator],
+        amount: u128,
+        _cursor: u64,
+    ) -> StdResult<Allocations> {
+        if validators.is_empty() {
+            return Err(StdError::generic_err(
+                "Failed to get validator to stake - no active validators",
+            ));
+        }
+
+        let mut ordered = validators.to_vec();
+        ordered.sort();
+
+        let total_after: u128 = ordered.iter().map(|v| v.staked).sum::<u128>() + amount;
+        let cap = total_after.saturating_mul(self.max_share as u128) / FEE_RESOLUTION;
+
+        let mut allocations: Allocations = vec![];
+        let mut remaining = amount;
+
+        for validator in ordered.iter() {
+            if remaining == 0 {
+                break;
+            }
+            let room = cap.saturating_sub(validator.staked);
+            let to_stake = min(room, remaining);
+            if to_stake > 0 {
+                add_allocation(&mut allocations, &validator.address, to_stake);
+                remaining -= to_stake;
+            }
+        }
+
+        // the cap is too tight for the current set - spread what's left by weight
+        if remaining > 0 {
+            let shares: Vec<(String, u128)> = ordered
+                .iter()
+                .map(|v| (v.address.clone(), v.weight as u128))
+                .collect();
+            for (address, amount) in split_by_shares(&shares, remaining).unwrap_or_default() {
+                add_allocation(&mut allocations, &address, amount);
+            }
+        }
+
+        Ok(allocations)
+    }
+
+    fn allocate_unbond(
+        &self,
+        validators: &[Validator],
+        amount: u128,
+        cursor: u64,
+    ) -> StdResult<Allocations> {
+        WeightedLowestStake.allocate_unbond(validators, amount, cursor)
+    }
+}
+
+pub fn add_allocation(allocations: &mut Allocations, address: &str, amount: u128) {
+    if let Some(existing) = allocations.iter_mut().find(|(a, _)| a == address) {
+        existing.1 += amount;
+    } else {
+        allocations.push((address.to_string(), amount));
+    }
+}
+
+/// splits `amount` according to `shares`. Rounding leftovers go to the largest share, so the sum
+/// of the result is always exactly `amount`. Returns None if all shares are 0
+pub fn split_by_shares(shares: &[(String, u128)], amount: u128) -> Option<Allocations> {
+    let total_shares: u128 = shares.iter().map(|(_, share)| share).sum();
+    if total_shares == 0 {
+        return None;
+    }
+
+    let mut allocations: Allocations = vec![];
+    let mut allocated: u128 = 0;
+
+    for (address, share) in shares {
+        let part = amount.saturating_mul(*share) / total_shares;
+        if part > 0 {
+            allocations.push((address.clone(), part));
+            allocated += part;
+        }
+    }
+
+    let leftover = amount - allocated;
+    if leftover > 0 {
+        let (largest, _) = shares.iter().max_by(|a, b| a.1.cmp(&b.1))?;
+        add_allocation(&mut allocations, largest, leftover);
+    }
+
+    Some(allocations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::types::validator_status::ValidatorStatus;
+
+    fn validator(address: &str, staked: u128, weight: u8) -> Validator {
+        Validator {
+            address: address.to_string(),
+            staked,
+            weight,
+            status: ValidatorStatus::Active.into(),
+            redelegation_cooldown: 0,
+            preferred: 0,
+            unbonding: vec![],
+            last_sync: 0,
+        }
+    }
+
+    fn shares(shares: &[(&str, u128)]) -> Vec<(String, u128)> {
+        shares
+            .iter()
+            .map(|(address, share)| (address.to_string(), *share))
+            .collect()
+    }
+
+    fn total(allocations: &[(String, u128)]) -> u128 {
+        allocations.iter().map(|(_, amount)| amount).sum()
+    }
+
+    #[test]
+    fn test_split_by_shares_rounding() {
+        // 3 each, the leftover goes to the largest share
+        assert_eq!(
+            split_by_shares(&shares(&[("a", 1), ("b", 1), ("c", 2)]), 10).unwrap(),
+            shares(&[("a", 2), ("b", 2), ("c", 6)])
+        );
+        assert_eq!(
+            split_by_shares(&shares(&[("a", 1), ("b", 1), ("c", 1)]), 10).map(|a| total(&a)),
+            Some(10)
+        );
+    }
+
+    #[test]
+    fn test_split_by_shares_zero_shares() {
+        assert_eq!(split_by_shares(&shares(&[("a", 0), ("b", 0)]), 10), None);
+        assert_eq!(split_by_shares(&shares(&[]), 10), None);
+        assert_eq!(
+            split_by_shares(&shares(&[("a", 0), ("b", 5)]), 7).unwrap(),
+            shares(&[("b", 7)])
+        );
+    }
+
+    #[test]
+    fn test_add_allocation() {
+        let mut allocations: Allocations = vec![];
+        add_allocation(&mut allocations, "a", 5);
+        add_allocation(&mut allocations, "b", 1);
+        add_allocation(&mut allocations, "a", 2);
+        assert_eq!(allocations, shares(&[("a", 7), ("b", 1)]));
+    }
+
+    #[test]
+    fn test_weighted_lowest_stake() {
+        let validators = vec![
+            validator("a", 100, 10),
+            validator("b", 150, 20),
+            validator("c", 90, 5),
+        ];
+
+        // b has the least stake per weight
+        assert_eq!(
+            WeightedLowestStake
+                .allocate_stake(&validators, 1_000, 0)
+                .unwrap(),
+            shares(&[("b", 1_000)])
+        );
+
+        // c has the most - never more than half of it at a time
+        let unbonds = WeightedLowestStake
+            .allocate_unbond(&validators, 60, 0)
+            .unwrap();
+        assert_eq!(unbonds, shares(&[("c", 45), ("a", 15)]));
+
+        // asks for more than there is
+        let unbonds = WeightedLowestStake
+            .allocate_unbond(&validators, 1_000, 0)
+            .unwrap();
+        assert_eq!(total(&unbonds), 340);
+        assert!(WeightedLowestStake.allocate_stake(&[], 1, 0).is_err());
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let validators = vec![
+            validator("c", 10, 10),
+            validator("a", 0, 10),
+            validator("b", 30, 10),
+        ];
+
+        // ordered by address, whatever the order of the set
+        let staked_at: Vec<String> = (0..4)
+            .map(|cursor| {
+                RoundRobin.allocate_stake(&validators, 1, cursor).unwrap()[0]
+                    .0
+                    .clone()
+            })
+            .collect();
+        assert_eq!(staked_at, vec!["a", "b", "c", "a"]);
+
+        // unbonds start at the cursor and wrap around
+        assert_eq!(
+            RoundRobin.allocate_unbond(&validators, 35, 1).unwrap(),
+            shares(&[("b", 30), ("c", 5)])
+        );
+        assert_eq!(
+            RoundRobin.allocate_unbond(&validators, 35, 2).unwrap(),
+            shares(&[("c", 10), ("b", 25)])
+        );
+    }
+
+    #[test]
+    fn test_proportional() {
+        let validators = vec![
+            validator("a", 300, 10),
+            validator("b", 100, 30),
+            validator("zero", 0, 0),
+        ];
+
+        // zero weight, nothing new
+        assert_eq!(
+            Proportional.allocate_stake(&validators, 100, 0).unwrap(),
+            shares(&[("a", 25), ("b", 75)])
+        );
+        assert!(Proportional
+            .allocate_stake(&[validator("zero", 0, 0)], 100, 0)
+            .is_err());
+
+        // unbonds follow the stake, and stop at what there is
+        assert_eq!(
+            Proportional.allocate_unbond(&validators, 100, 0).unwrap(),
+            shares(&[("a", 75), ("b", 25)])
+        );
+        assert_eq!(
+            Proportional.allocate_unbond(&validators, 1_000, 0).unwrap(),
+            shares(&[("a", 300), ("b", 100)])
+        );
+    }
+
+    #[test]
+    fn test_concentration_capped() {
+        let strategy = ConcentrationCapped { max_share: 50_000 };
+        let validators = vec![validator("a", 500, 10), validator("b", 100, 10)];
+
+        // 1000 afterwards, so nobody goes above 500 - b fills up first, a is already there
+        assert_eq!(
+            strategy.allocate_stake(&validators, 400, 0).unwrap(),
+            shares(&[("b", 400)])
+        );
+
+        // 1300 afterwards caps at 650 - b fills to it, then a takes the rest
+        assert_eq!(
+            strategy.allocate_stake(&validators, 700, 0).unwrap(),
+            shares(&[("b", 550), ("a", 150)])
+        );
+    }
+
+    #[test]
+    fn test_concentration_capped_when_everyone_is_at_the_cap() {
+        let strategy = ConcentrationCapped { max_share: 40_000 };
+        let validators = vec![validator("a", 500, 10), validator("b", 500, 30)];
+
+        // the cap is 440, below what both already have - spread by weight
+        assert_eq!(
+            strategy.allocate_stake(&validators, 100, 0).unwrap(),
+            shares(&[("b", 75), ("a", 25)])
+        );
+    }
+}
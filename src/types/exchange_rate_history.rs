@@ -0,0 +1,170 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdError, StdResult, Storage};
+use cosmwasm_storage::{PrefixedStorage, ReadonlyPrefixedStorage, ReadonlySingleton, Singleton};
+use rust_decimal::prelude::{FromStr, Zero};
+use rust_decimal::Decimal;
+use secret_toolkit::storage::{AppendStore, AppendStoreMut};
+
+pub const EXCHANGE_RATE_HISTORY: &[u8] = b"exchange_rate_history";
+pub static KEY_EXCHANGE_RATE_HISTORY_HEAD: &[u8] = b"exchange_rate_history_head";
+
+/// once the history is full, every new rate overwrites the oldest one
+pub const MAX_EXCHANGE_RATE_HISTORY: u32 = 1_000;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExchangeRatePoint {
+    pub time: u64,
+    pub height: u64,
+    /// SCRT per token
+    pub rate: String,
+    /// sum of rate * seconds since the first recorded rate, up to `time`. The average rate
+    /// between two points is the difference of their cumulative rates over the time between them
+    pub cumulative: String,
+}
+
+impl ExchangeRatePoint {
+    fn rate(&self) -> StdResult<Decimal> {
+        parse_decimal(&self.rate)
+    }
+
+    /// the cumulative rate at `time`, which must not be before this point. The rate holds until
+    /// the next point is recorded
+    pub fn cumulative_at(&self, time: u64) -> StdResult<Decimal> {
+        Ok(parse_decimal(&self.cumulative)?
+            + self.rate()? * Decimal::from(time.saturating_sub(self.time)))
+    }
+}
+
+/// Appends a rate to the ring buffer, and returns the new point. A second rate in the same block
+/// replaces the first, so the history stays strictly ordered by time
+pub fn append_exchange_rate<S: Storage>(
+    storage: &mut S,
+    time: u64,
+    height: u64,
+    rate: Decimal,
+) -> StdResult<ExchangeRatePoint> {
+    // position of the oldest entry. Stays 0 until the buffer is full
+    let head: u32 = ReadonlySingleton::new(storage, KEY_EXCHANGE_RATE_HISTORY_HEAD)
+        .may_load()?
+        .unwrap_or_default();
+
+    let (point, new_head) = {
+        let mut store = PrefixedStorage::new(EXCHANGE_RATE_HISTORY, storage);
+        let mut store: AppendStoreMut<ExchangeRatePoint, PrefixedStorage<S>> =
+            AppendStoreMut::attach_or_create(&mut store)?;
+        let len = store.len();
+
+        let mut point = ExchangeRatePoint {
+            time,
+            height,
+            rate: rate.to_string(),
+            cumulative: Decimal::zero().to_string(),
+        };
+
+        if len > 0 {
+            let newest_pos = (head + len - 1) % len;
+            let newest = store.get_at(newest_pos)?;
+            if newest.time == time {
+                point.cumulative = newest.cumulative;
+                store.set_at(newest_pos, &point)?;
+                return Ok(point);
+            }
+            point.cumulative = newest.cumulative_at(time)?.to_string();
+        }
+
+        if len < MAX_EXCHANGE_RATE_HISTORY {
+            store.push(&point)?;
+            (point, head)
+        } else {
+            store.set_at(head, &point)?;
+            (point, (head + 1) % MAX_EXCHANGE_RATE_HISTORY)
+        }
+    };
+
+    if new_head != head {
+        Singleton::new(storage, KEY_EXCHANGE_RATE_HISTORY_HEAD).save(&new_head)?;
+    }
+
+    Ok(point)
+}
+
+/// The time-weighted average rate over the `period` seconds that end at the newest recorded
+/// rate. If the history doesn't reach that far back, the average covers the whole history.
+/// Returns None while nothing was recorded
+pub fn time_weighted_rate<S: Storage>(storage: &S, period: u64) -> StdResult<Option<Decimal>> {
+    let head: u32 = ReadonlySingleton::new(storage, KEY_EXCHANGE_RATE_HISTORY_HEAD)
+        .may_load()?
+        .unwrap_or_default();
+
+    let store = ReadonlyPrefixedStorage::new(EXCHANGE_RATE_HISTORY, storage);
+    let store: AppendStore<ExchangeRatePoint, ReadonlyPrefixedStorage<S>> =
+        match AppendStore::attach(&store) {
+            Some(store) => store?,
+            None => return Ok(None),
+        };
+    let len = store.len();
+    if len == 0 {
+        return Ok(None);
+    }
+
+    let newest = store.get_at((head + len - 1) % len)?;
+    let start = newest.time.saturating_sub(period);
+
+    // walk back to the last point recorded at or before the start of the period
+    let mut from = newest.clone();
+    for i in (0..len - 1).rev() {
+        if from.time <= start {
+            break;
+        }
+        from = store.get_at((head + i) % len)?;
+    }
+
+    let start = std::cmp::max(start, from.time);
+    if newest.time == start {
+        return Ok(Some(newest.rate()?));
+    }
+
+    let elapsed = Decimal::from(newest.time - start);
+    Ok(Some(
+        (newest.cumulative_at(newest.time)? - from.cumulative_at(start)?) / elapsed,
+    ))
+}
+
+fn parse_decimal(value: &str) -> StdResult<Decimal> {
+    Decimal::from_str(value)
+        .map_err(|_| StdError::generic_err("Failed to parse exchange rate history"))
+}
+
+/// oldest first. Only rates recorded after `start_after` (a block time) are returned
+pub fn read_exchange_rate_history<S: Storage>(
+    storage: &S,
+    start_after: Option<u64>,
+    limit: u32,
+) -> StdResult<Vec<ExchangeRatePoint>> {
+    let head: u32 = ReadonlySingleton::new(storage, KEY_EXCHANGE_RATE_HISTORY_HEAD)
+        .may_load()?
+        .unwrap_or_default();
+
+    let store = ReadonlyPrefixedStorage::new(EXCHANGE_RATE_HISTORY, storage);
+    let store: AppendStore<ExchangeRatePoint, ReadonlyPrefixedStorage<S>> =
+        match AppendStore::attach(&store) {
+            Some(store) => store?,
+            None => return Ok(vec![]),
+        };
+    let len = store.len();
+
+    let mut points: Vec<ExchangeRatePoint> = vec![];
+    for i in 0..len {
+        if points.len() >= limit as usize {
+            break;
+        }
+        let point = store.get_at((head + i) % len)?;
+        if start_after.map_or(true, |after| point.time > after) {
+            points.push(point);
+        }
+    }
+
+    Ok(points)
+}
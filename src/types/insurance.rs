@@ -0,0 +1,72 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdResult, Storage, Uint128};
+use cosmwasm_storage::{ReadonlySingleton, Singleton};
+
+pub static KEY_INSURANCE_FUND: &[u8] = b"insurance_fund";
+
+/// we keep the last 100 payouts
+pub const MAX_INSURANCE_PAYOUTS: usize = 100;
+
+/// A slash the reserve paid for. `covered` can be less than `slashed` if the reserve ran dry
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct InsurancePayout {
+    pub validator: String,
+    pub slashed: Uint128,
+    pub covered: Uint128,
+    pub time: u64,
+    pub height: u64,
+}
+
+/// SCRT set aside from the deposit fees to cover slashes. The reserve stays in the contract's
+/// balance, but it doesn't back the tokens until it's paid out
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, Default)]
+pub struct InsuranceFund {
+    /// share of the deposit fee that goes to the reserve, in the same units as the dev fee
+    #[serde(default)]
+    pub fee_share: u64,
+    #[serde(default)]
+    pub reserve: Uint128,
+    #[serde(default)]
+    pub total_paid_out: Uint128,
+    #[serde(default)]
+    pub payouts: Vec<InsurancePayout>,
+}
+
+impl InsuranceFund {
+    /// covers as much of a slash as the reserve allows, and returns the amount covered
+    pub fn cover(&mut self, validator: &str, slashed: u128, time: u64, height: u64) -> u128 {
+        let covered = std::cmp::min(slashed, self.reserve.u128());
+        if covered == 0 {
+            return 0;
+        }
+
+        self.reserve = Uint128(self.reserve.u128() - covered);
+        self.total_paid_out = Uint128(self.total_paid_out.u128() + covered);
+        self.payouts.push(InsurancePayout {
+            validator: validator.to_string(),
+            slashed: Uint128(slashed),
+            covered: Uint128(covered),
+            time,
+            height,
+        });
+
+        if self.payouts.len() > MAX_INSURANCE_PAYOUTS {
+            let excess = self.payouts.len() - MAX_INSURANCE_PAYOUTS;
+            self.payouts.drain(..excess);
+        }
+
+        covered
+    }
+}
+
+pub fn set_insurance_fund<S: Storage>(storage: &mut S, data: &InsuranceFund) -> StdResult<()> {
+    Singleton::new(storage, KEY_INSURANCE_FUND).save(data)
+}
+
+pub fn read_insurance_fund<S: Storage>(storage: &S) -> StdResult<InsuranceFund> {
+    Ok(ReadonlySingleton::new(storage, KEY_INSURANCE_FUND)
+        .may_load()?
+        .unwrap_or_default())
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{ReadonlyStorage, StdError, StdResult, Storage};
+use cosmwasm_storage::ReadonlyPrefixedStorage;
+use std::collections::VecDeque;
+
+use crate::types::config::PREFIX_CONFIG;
+use crate::types::validator_set::{Validator, ValidatorSet, KEY_VALIDATOR_SET};
+use crate::types::validator_status::ValidatorStatus;
+
+// The layout the validator set was stored in by the first release. bincode isn't
+// self-describing, so it's kept as it was
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ValidatorV1 {
+    pub address: String,
+    pub staked: u128,
+    pub weight: u8,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ValidatorSetV1 {
+    pub validators: VecDeque<ValidatorV1>,
+}
+
+impl From<ValidatorSetV1> for ValidatorSet {
+    fn from(set: ValidatorSetV1) -> Self {
+        let validators = set
+            .validators
+            .into_iter()
+            .map(|v| Validator {
+                address: v.address,
+                staked: v.staked,
+                weight: v.weight,
+                status: ValidatorStatus::Active.into(),
+                redelegation_cooldown: 0,
+                preferred: 0,
+                unbonding: vec![],
+                last_sync: 0,
+            })
+            .collect();
+
+        ValidatorSet::from_parts(validators, 0)
+    }
+}
+
+/// Reads a validator set stored by the first release and upgrades it to the current layout
+pub fn load_legacy_validator_set<S: Storage>(store: &S) -> StdResult<ValidatorSet> {
+    let config_store = ReadonlyPrefixedStorage::new(PREFIX_CONFIG, store);
+    let bytes = config_store
+        .get(KEY_VALIDATOR_SET)
+        .ok_or_else(|| StdError::generic_err("There is no stored validator set"))?;
+
+    let set: ValidatorSetV1 = bincode2::deserialize(&bytes)
+        .map_err(|_| StdError::generic_err("The stored validator set is not in the v1 layout"))?;
+
+    Ok(set.into())
+}
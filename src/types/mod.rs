@@ -1,9 +1,29 @@
 pub(crate) mod activation_fee;
+pub(crate) mod claims_ledger;
+pub(crate) mod compounding;
 pub(crate) mod config;
+pub(crate) mod contract_version;
+pub(crate) mod delegation_strategy;
+pub(crate) mod exchange_rate_history;
+pub(crate) mod insurance;
 pub(crate) mod killswitch;
+pub(crate) mod legacy_validator_set;
+pub(crate) mod pause;
+pub(crate) mod pending_restake;
 pub(crate) mod pending_withdraw;
+pub(crate) mod rate_guard;
+pub(crate) mod rate_subscribers;
+pub(crate) mod roles;
+pub(crate) mod scoring_config;
 pub(crate) mod shared_withdraw_config;
+pub(crate) mod slashing;
+pub(crate) mod timelock;
 pub(crate) mod user_withdraws;
+pub(crate) mod validator_performance;
+pub(crate) mod validator_policy;
 pub(crate) mod validator_set;
+pub(crate) mod validator_status;
+pub(crate) mod wind_down;
 pub(crate) mod window_manager;
 pub(crate) mod withdraw_window;
+pub(crate) mod yield_ledger;
@@ -0,0 +1,51 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdResult, Storage};
+use cosmwasm_storage::{ReadonlySingleton, Singleton};
+
+pub static KEY_PAUSE_FLAGS: &[u8] = b"pause_flags";
+
+/// Parts of the contract that are paused. Unlike the kill switch these can be lifted again - the
+/// guardian can pause, only the admin can unpause
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, Default, JsonSchema)]
+pub struct PauseFlags {
+    #[serde(default)]
+    pub deposits: bool,
+    #[serde(default)]
+    pub withdraws: bool,
+    #[serde(default)]
+    pub claims: bool,
+    /// adding, removing, reweighting and redelegating validators, by the admin or by anyone
+    /// (rebalancing, evacuating, syncing, refreshing weights, migrating the set, compounding)
+    #[serde(default)]
+    pub validator_ops: bool,
+}
+
+impl PauseFlags {
+    /// sets every flag that is set in `other`
+    pub fn pause(&mut self, other: &PauseFlags) {
+        self.deposits |= other.deposits;
+        self.withdraws |= other.withdraws;
+        self.claims |= other.claims;
+        self.validator_ops |= other.validator_ops;
+    }
+
+    /// clears every flag that is set in `other`
+    pub fn unpause(&mut self, other: &PauseFlags) {
+        self.deposits &= !other.deposits;
+        self.withdraws &= !other.withdraws;
+        self.claims &= !other.claims;
+        self.validator_ops &= !other.validator_ops;
+    }
+}
+
+pub fn set_pause_flags<S: Storage>(storage: &mut S, data: &PauseFlags) -> StdResult<()> {
+    Singleton::new(storage, KEY_PAUSE_FLAGS).save(data)
+}
+
+pub fn read_pause_flags<S: Storage>(storage: &S) -> StdResult<PauseFlags> {
+    Ok(ReadonlySingleton::new(storage, KEY_PAUSE_FLAGS)
+        .may_load()?
+        .unwrap_or_default())
+}
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdResult, Storage, Uint128};
+use cosmwasm_storage::{ReadonlySingleton, Singleton};
+
+pub static KEY_PENDING_RESTAKE: &[u8] = b"pending_restake";
+
+/// Stake that had to be undelegated instead of redelegated (because the source validator was
+/// still blocked by an earlier incoming redelegation). It's still ours, and gets restaked once
+/// the unbonding period is over
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq)]
+pub struct RestakeEntry {
+    pub amount: Uint128,
+    pub available_time: u64,
+}
+
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, Default)]
+pub struct PendingRestake {
+    pub entries: Vec<RestakeEntry>,
+}
+
+impl PendingRestake {
+    pub fn total(&self) -> u128 {
+        self.entries.iter().map(|e| e.amount.u128()).sum()
+    }
+
+    pub fn append(&mut self, amount: u128, available_time: u64) {
+        self.entries.push(RestakeEntry {
+            amount: Uint128(amount),
+            available_time,
+        })
+    }
+
+    /// removes all the entries that have finished unbonding and returns their total
+    pub fn take_matured(&mut self, current_time: u64) -> u128 {
+        let matured: u128 = self
+            .entries
+            .iter()
+            .filter(|e| e.available_time <= current_time)
+            .map(|e| e.amount.u128())
+            .sum();
+
+        self.entries.retain(|e| e.available_time > current_time);
+
+        matured
+    }
+}
+
+pub fn set_pending_restake<S: Storage>(storage: &mut S, data: &PendingRestake) -> StdResult<()> {
+    Singleton::new(storage, KEY_PENDING_RESTAKE).save(data)
+}
+
+pub fn read_pending_restake<S: Storage>(storage: &S) -> StdResult<PendingRestake> {
+    Ok(ReadonlySingleton::new(storage, KEY_PENDING_RESTAKE)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+pub fn take_matured_restake<S: Storage>(storage: &mut S, current_time: u64) -> StdResult<u128> {
+    let mut pending = read_pending_restake(storage)?;
+
+    let matured = pending.take_matured(current_time);
+    if matured > 0 {
+        set_pending_restake(storage, &pending)?;
+    }
+
+    Ok(matured)
+}
@@ -0,0 +1,80 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdResult, Storage};
+use cosmwasm_storage::{ReadonlySingleton, Singleton};
+
+pub static KEY_RATE_GUARD: &[u8] = b"rate_guard";
+
+/// 2%, in the same units as the dev fee
+pub const DEFAULT_MAX_CHANGE_PER_WINDOW: u64 = 2_000;
+/// 0.1%
+pub const DEFAULT_MAX_CHANGE_PER_BLOCK: u64 = 100;
+
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct RateReference {
+    /// SCRT per token
+    pub rate: String,
+    pub time: u64,
+    pub height: u64,
+}
+
+/// Stored by `raise_rate_alert` when the rate is outside of the bounds. Deposits and withdraws
+/// stay refused until the guardian or the admin overrides it
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct RateAlert {
+    pub rate: String,
+    pub reference: String,
+    /// the change that tripped the guard, in the same units as the dev fee
+    pub change: u64,
+    pub time: u64,
+    pub height: u64,
+}
+
+/// Bounds on how fast the exchange rate may move. Changes are relative, in the same units as the
+/// dev fee. The per-block bound scales with the number of blocks since the rate was last seen
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq)]
+pub struct RateGuard {
+    #[serde(default = "default_max_change_per_window")]
+    pub max_change_per_window: u64,
+    #[serde(default = "default_max_change_per_block")]
+    pub max_change_per_block: u64,
+    /// the rate when the current window started
+    #[serde(default)]
+    pub window_reference: Option<RateReference>,
+    /// the rate the last deposit or withdraw was priced at
+    #[serde(default)]
+    pub last_seen: Option<RateReference>,
+    #[serde(default)]
+    pub alert: Option<RateAlert>,
+}
+
+impl Default for RateGuard {
+    fn default() -> Self {
+        Self {
+            max_change_per_window: DEFAULT_MAX_CHANGE_PER_WINDOW,
+            max_change_per_block: DEFAULT_MAX_CHANGE_PER_BLOCK,
+            window_reference: None,
+            last_seen: None,
+            alert: None,
+        }
+    }
+}
+
+fn default_max_change_per_window() -> u64 {
+    DEFAULT_MAX_CHANGE_PER_WINDOW
+}
+
+fn default_max_change_per_block() -> u64 {
+    DEFAULT_MAX_CHANGE_PER_BLOCK
+}
+
+pub fn set_rate_guard<S: Storage>(storage: &mut S, data: &RateGuard) -> StdResult<()> {
+    Singleton::new(storage, KEY_RATE_GUARD).save(data)
+}
+
+pub fn read_rate_guard<S: Storage>(storage: &S) -> StdResult<RateGuard> {
+    Ok(ReadonlySingleton::new(storage, KEY_RATE_GUARD)
+        .may_load()?
+        .unwrap_or_default())
+}
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+use cargo_common::contract::Contract;
+use cosmwasm_std::{StdResult, Storage};
+use cosmwasm_storage::{ReadonlySingleton, Singleton};
+
+pub static KEY_RATE_SUBSCRIBERS: &[u8] = b"rate_subscribers";
+
+/// every subscriber adds a message to compounds
+pub const MAX_RATE_SUBSCRIBERS: usize = 10;
+
+/// Contracts that are sent a RateUpdate every time a compound records the exchange rate
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, Default)]
+pub struct RateSubscribers {
+    pub contracts: Vec<Contract>,
+}
+
+pub fn set_rate_subscribers<S: Storage>(storage: &mut S, data: &RateSubscribers) -> StdResult<()> {
+    Singleton::new(storage, KEY_RATE_SUBSCRIBERS).save(data)
+}
+
+pub fn read_rate_subscribers<S: Storage>(storage: &S) -> StdResult<RateSubscribers> {
+    Ok(ReadonlySingleton::new(storage, KEY_RATE_SUBSCRIBERS)
+        .may_load()?
+        .unwrap_or_default())
+}
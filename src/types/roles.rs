@@ -0,0 +1,77 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{HumanAddr, StdResult, Storage};
+use cosmwasm_storage::{ReadonlySingleton, Singleton};
+
+pub static KEY_ROLES: &[u8] = b"roles";
+
+/// Roles the owner can grant. The owner (the config admin) can do everything a role can
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// adds, removes and reweights validators, and sets the validator policy
+    ValidatorManager,
+    /// sets the dev fee, the compound bounty and the insurance share
+    FeeManager,
+    /// pauses the contract and overrides the rate guard - can't unpause or move funds
+    Guardian,
+    /// runs the admin's maintenance, like claiming matured withdraws for everyone
+    Keeper,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoleMember {
+    pub address: HumanAddr,
+    pub role: Role,
+}
+
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, Default)]
+pub struct Roles {
+    #[serde(default)]
+    pub members: Vec<RoleMember>,
+}
+
+impl Roles {
+    pub fn has(&self, address: &HumanAddr, role: Role) -> bool {
+        self.members
+            .iter()
+            .any(|m| &m.address == address && m.role == role)
+    }
+
+    pub fn grant(&mut self, address: HumanAddr, role: Role) {
+        if !self.has(&address, role) {
+            self.members.push(RoleMember { address, role });
+        }
+    }
+
+    /// returns false if the address didn't have the role
+    pub fn revoke(&mut self, address: &HumanAddr, role: Role) -> bool {
+        let before = self.members.len();
+        self.members
+            .retain(|m| !(&m.address == address && m.role == role));
+        self.members.len() != before
+    }
+
+    pub fn holders(&self, role: Role) -> Vec<HumanAddr> {
+        self.members
+            .iter()
+            .filter(|m| m.role == role)
+            .map(|m| m.address.clone())
+            .collect()
+    }
+}
+
+pub fn set_roles<S: Storage>(storage: &mut S, data: &Roles) -> StdResult<()> {
+    Singleton::new(storage, KEY_ROLES).save(data)
+}
+
+pub fn read_roles<S: Storage>(storage: &S) -> StdResult<Roles> {
+    Ok(ReadonlySingleton::new(storage, KEY_ROLES)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+pub fn has_role<S: Storage>(storage: &S, address: &HumanAddr, role: Role) -> StdResult<bool> {
+    Ok(read_roles(storage)?.has(address, role))
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+use cargo_common::contract::Contract;
+use cosmwasm_std::{StdResult, Storage};
+use cosmwasm_storage::{ReadonlySingleton, Singleton};
+
+use crate::types::validator_set::DEFAULT_WEIGHT;
+
+pub static KEY_SCORING_CONFIG: &[u8] = b"scoring_config";
+
+pub const DEFAULT_MIN_WEIGHT: u8 = 1;
+
+/// Bounds for the weights the automated scoring may assign. The oracle supplies the stats the
+/// chain querier doesn't expose (voting power, uptime, jail history)
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq)]
+pub struct ScoringConfig {
+    #[serde(default)]
+    pub oracle: Option<Contract>,
+    pub min_weight: u8,
+    pub max_weight: u8,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        Self {
+            oracle: None,
+            min_weight: DEFAULT_MIN_WEIGHT,
+            max_weight: DEFAULT_WEIGHT,
+        }
+    }
+}
+
+pub fn set_scoring_config<S: Storage>(storage: &mut S, data: &ScoringConfig) -> StdResult<()> {
+    Singleton::new(storage, KEY_SCORING_CONFIG).save(data)
+}
+
+pub fn read_scoring_config<S: Storage>(storage: &S) -> StdResult<ScoringConfig> {
+    Ok(ReadonlySingleton::new(storage, KEY_SCORING_CONFIG)
+        .may_load()?
+        .unwrap_or_default())
+}
@@ -0,0 +1,62 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdResult, Storage, Uint128};
+use cosmwasm_storage::{ReadonlySingleton, Singleton};
+
+pub static KEY_SLASH_HISTORY: &[u8] = b"slash_history";
+
+/// we keep the last 100 slashes
+pub const MAX_SLASH_EVENTS: usize = 100;
+
+/// differences this small are share rounding, not slashes, and are neither recorded nor insured
+pub const SLASH_TOLERANCE: u128 = 1_000;
+
+/// A difference between our bookkeeping and the real delegation, found by a delegation sync
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct SlashEvent {
+    pub validator: String,
+    pub amount: Uint128,
+    pub time: u64,
+    pub height: u64,
+}
+
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, Default)]
+pub struct SlashHistory {
+    #[serde(default)]
+    pub events: Vec<SlashEvent>,
+    /// the total of the events that were dropped to stay within MAX_SLASH_EVENTS
+    #[serde(default)]
+    pub pruned: Uint128,
+}
+
+impl SlashHistory {
+    pub fn total(&self) -> u128 {
+        self.pruned.u128() + self.events.iter().map(|e| e.amount.u128()).sum::<u128>()
+    }
+
+    pub fn record(&mut self, validator: &str, amount: u128, time: u64, height: u64) {
+        self.events.push(SlashEvent {
+            validator: validator.to_string(),
+            amount: Uint128(amount),
+            time,
+            height,
+        });
+
+        if self.events.len() > MAX_SLASH_EVENTS {
+            let excess = self.events.len() - MAX_SLASH_EVENTS;
+            let dropped: u128 = self.events.drain(..excess).map(|e| e.amount.u128()).sum();
+            self.pruned = Uint128(self.pruned.u128() + dropped);
+        }
+    }
+}
+
+pub fn set_slash_history<S: Storage>(storage: &mut S, data: &SlashHistory) -> StdResult<()> {
+    Singleton::new(storage, KEY_SLASH_HISTORY).save(data)
+}
+
+pub fn read_slash_history<S: Storage>(storage: &S) -> StdResult<SlashHistory> {
+    Ok(ReadonlySingleton::new(storage, KEY_SLASH_HISTORY)
+        .may_load()?
+        .unwrap_or_default())
+}
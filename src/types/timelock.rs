@@ -0,0 +1,110 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{HumanAddr, StdError, StdResult, Storage};
+use cosmwasm_storage::{ReadonlySingleton, Singleton};
+
+use crate::msg::HandleMsg;
+
+pub static KEY_TIMELOCK: &[u8] = b"timelock";
+pub static KEY_PENDING_OWNER: &[u8] = b"pending_owner";
+
+/// 2 days
+pub const DEFAULT_TIMELOCK_DELAY: u64 = 3600 * 24 * 2;
+/// queued actions that were neither executed nor cancelled pile up - cap them
+pub const MAX_QUEUED_ACTIONS: usize = 20;
+
+/// A sensitive admin command waiting for its delay to pass
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+pub struct QueuedAction {
+    pub id: u64,
+    pub msg: HandleMsg,
+    pub proposer: HumanAddr,
+    pub proposed_at: u64,
+    /// the action can't be executed before this time
+    pub eta: u64,
+}
+
+/// Sensitive admin commands have to be queued, and can only be executed once `delay` passed.
+/// Until then, anyone can see them coming and they can still be cancelled
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Timelock {
+    #[serde(default = "default_delay")]
+    pub delay: u64,
+    #[serde(default)]
+    pub next_id: u64,
+    #[serde(default)]
+    pub queue: Vec<QueuedAction>,
+}
+
+impl Default for Timelock {
+    fn default() -> Self {
+        Self {
+            delay: DEFAULT_TIMELOCK_DELAY,
+            next_id: 0,
+            queue: vec![],
+        }
+    }
+}
+
+fn default_delay() -> u64 {
+    DEFAULT_TIMELOCK_DELAY
+}
+
+impl Timelock {
+    pub fn propose(
+        &mut self,
+        msg: HandleMsg,
+        proposer: HumanAddr,
+        current_time: u64,
+    ) -> StdResult<&QueuedAction> {
+        if self.queue.len() >= MAX_QUEUED_ACTIONS {
+            return Err(StdError::generic_err(format!(
+                "There are already {} actions queued",
+                MAX_QUEUED_ACTIONS
+            )));
+        }
+
+        self.queue.push(QueuedAction {
+            id: self.next_id,
+            msg,
+            proposer,
+            proposed_at: current_time,
+            eta: current_time + self.delay,
+        });
+        self.next_id += 1;
+
+        Ok(self.queue.last().unwrap())
+    }
+
+    /// removes the action from the queue and returns it
+    pub fn take(&mut self, id: u64) -> StdResult<QueuedAction> {
+        let pos =
+            self.queue.iter().position(|a| a.id == id).ok_or_else(|| {
+                StdError::generic_err(format!("There is no queued action {}", id))
+            })?;
+
+        Ok(self.queue.remove(pos))
+    }
+}
+
+pub fn set_timelock<S: Storage>(storage: &mut S, data: &Timelock) -> StdResult<()> {
+    Singleton::new(storage, KEY_TIMELOCK).save(data)
+}
+
+pub fn read_timelock<S: Storage>(storage: &S) -> StdResult<Timelock> {
+    Ok(ReadonlySingleton::new(storage, KEY_TIMELOCK)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+/// the address the owner proposed to hand the contract over to, until it accepts
+pub fn set_pending_owner<S: Storage>(storage: &mut S, owner: &Option<HumanAddr>) -> StdResult<()> {
+    Singleton::new(storage, KEY_PENDING_OWNER).save(owner)
+}
+
+pub fn read_pending_owner<S: Storage>(storage: &S) -> StdResult<Option<HumanAddr>> {
+    Ok(ReadonlySingleton::new(storage, KEY_PENDING_OWNER)
+        .may_load()?
+        .unwrap_or_default())
+}
@@ -84,6 +84,21 @@ impl UserWithdrawManager {
         return 0;
     }
 
+    /// everyone that still has an unclaimed withdraw in this window
+    pub fn users<S: ReadonlyStorage>(&self, store: &S) -> StdResult<Vec<HumanAddr>> {
+        let store = ReadonlyPrefixedStorage::multilevel(
+            &[WITHDRAWERS_FOR_WINDOW, &u64_to_bytes(&self.window)],
+            store,
+        );
+        let store: Option<StdResult<AppendStore<HumanAddr, ReadonlyPrefixedStorage<S>>>> =
+            AppendStore::attach(&store);
+
+        match store {
+            Some(store) => store?.iter().collect(),
+            None => Ok(vec![]),
+        }
+    }
+
     pub fn append<S: Storage>(&self, store: &mut S, user: &HumanAddr) -> StdResult<()> {
         let mut store = PrefixedStorage::multilevel(
             &[WITHDRAWERS_FOR_WINDOW, &u64_to_bytes(&self.window)],
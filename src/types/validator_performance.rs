@@ -0,0 +1,181 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cargo_common::cashmap::{CashMap, ReadOnlyCashMap};
+use cosmwasm_std::{StdResult, Storage, Uint128};
+
+use crate::types::validator_set::{get_validator_set, ValidatorSet};
+
+pub const VALIDATOR_PERFORMANCE: &[u8] = b"VALPERF";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CommissionChange {
+    pub time: u64,
+    /// same units as the dev fee. 10_000 = 10%
+    pub commission: u64,
+}
+
+/// Everything we know about how a validator performed for us. Entries are kept after a
+/// validator leaves the set
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct ValidatorPerformance {
+    pub address: String,
+    /// sum of stake * seconds it was staked, up to last_update
+    pub stake_time: Uint128,
+    pub harvested_rewards: Uint128,
+    pub commission_changes: Vec<CommissionChange>,
+    pub slashes: u32,
+    pub slashed_amount: Uint128,
+    /// stake since last_update
+    pub staked: Uint128,
+    pub last_update: u64,
+}
+
+impl ValidatorPerformance {
+    fn new(address: &str, current_time: u64) -> Self {
+        Self {
+            address: address.to_string(),
+            last_update: current_time,
+            ..Default::default()
+        }
+    }
+
+    /// accumulates stake-time up to now, and switches to the new stake from here on
+    pub fn advance(&mut self, staked: u128, current_time: u64) {
+        let elapsed = current_time.saturating_sub(self.last_update) as u128;
+
+        self.stake_time = Uint128(
+            self.stake_time
+                .u128()
+                .saturating_add(self.staked.u128().saturating_mul(elapsed)),
+        );
+        self.staked = Uint128(staked);
+        self.last_update = current_time;
+    }
+
+    /// returns false if the commission didn't change since it was last seen
+    pub fn record_commission(&mut self, commission: u64, current_time: u64) -> bool {
+        if self.commission_changes.last().map(|c| c.commission) == Some(commission) {
+            return false;
+        }
+
+        self.commission_changes.push(CommissionChange {
+            time: current_time,
+            commission,
+        });
+        true
+    }
+}
+
+/// Call this with the updated validator set every time stake moves, right before it's stored. It's
+/// compared with the stored set, so only the validators whose stake changed are read and written
+pub fn checkpoint_performance<S: Storage>(
+    storage: &mut S,
+    validator_set: &ValidatorSet,
+    current_time: u64,
+) -> StdResult<()> {
+    let stored = get_validator_set(storage)?;
+    let staked = |set: &ValidatorSet, address: &str| set.get(address).map(|v| v.staked);
+
+    let mut touched: Vec<String> = validator_set
+        .addresses()
+        .into_iter()
+        .filter(|address| staked(&stored, address) != staked(validator_set, address))
+        .collect();
+    // validators that left the set stop accruing stake-time
+    touched.extend(
+        stored
+            .addresses()
+            .into_iter()
+            .filter(|address| validator_set.get(address).is_none()),
+    );
+
+    let mut ledger: CashMap<ValidatorPerformance, S> =
+        CashMap::init(VALIDATOR_PERFORMANCE, storage);
+
+    for address in touched {
+        let mut entry = ledger
+            .get(address.as_bytes())
+            .unwrap_or_else(|| ValidatorPerformance::new(&address, current_time));
+        entry.advance(
+            staked(validator_set, &address).unwrap_or_default(),
+            current_time,
+        );
+        ledger.insert(address.as_bytes(), entry)?;
+    }
+
+    Ok(())
+}
+
+pub fn record_harvest<S: Storage>(
+    storage: &mut S,
+    address: &str,
+    amount: u128,
+    current_time: u64,
+) -> StdResult<()> {
+    update_performance(storage, address, current_time, |entry| {
+        entry.harvested_rewards = Uint128(entry.harvested_rewards.u128().saturating_add(amount));
+    })
+}
+
+pub fn record_slash<S: Storage>(
+    storage: &mut S,
+    address: &str,
+    amount: u128,
+    current_time: u64,
+) -> StdResult<()> {
+    update_performance(storage, address, current_time, |entry| {
+        entry.slashes += 1;
+        entry.slashed_amount = Uint128(entry.slashed_amount.u128().saturating_add(amount));
+    })
+}
+
+pub fn record_commission<S: Storage>(
+    storage: &mut S,
+    address: &str,
+    commission: u64,
+    current_time: u64,
+) -> StdResult<()> {
+    let mut ledger: CashMap<ValidatorPerformance, S> =
+        CashMap::init(VALIDATOR_PERFORMANCE, storage);
+
+    let mut entry = ledger
+        .get(address.as_bytes())
+        .unwrap_or_else(|| ValidatorPerformance::new(address, current_time));
+
+    if entry.record_commission(commission, current_time) {
+        ledger.insert(address.as_bytes(), entry)?;
+    }
+
+    Ok(())
+}
+
+/// starts from page 0
+pub fn read_performance_page<S: Storage>(
+    storage: &S,
+    page: u32,
+    page_size: u32,
+) -> StdResult<Vec<ValidatorPerformance>> {
+    let ledger: ReadOnlyCashMap<ValidatorPerformance, S> =
+        ReadOnlyCashMap::init(VALIDATOR_PERFORMANCE, storage);
+
+    ledger.paging(page, page_size)
+}
+
+fn update_performance<S: Storage, F: FnOnce(&mut ValidatorPerformance)>(
+    storage: &mut S,
+    address: &str,
+    current_time: u64,
+    update: F,
+) -> StdResult<()> {
+    let mut ledger: CashMap<ValidatorPerformance, S> =
+        CashMap::init(VALIDATOR_PERFORMANCE, storage);
+
+    let mut entry = ledger
+        .get(address.as_bytes())
+        .unwrap_or_else(|| ValidatorPerformance::new(address, current_time));
+
+    update(&mut entry);
+
+    ledger.insert(address.as_bytes(), entry)
+}
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdResult, Storage};
+use cosmwasm_storage::{ReadonlySingleton, Singleton};
+
+pub static KEY_VALIDATOR_POLICY: &[u8] = b"validator_policy";
+
+pub const DEFAULT_MAX_COMMISSION: u64 = 10_000; // 10%
+pub const DEFAULT_DRIFT_TOLERANCE: u64 = 1_000; // 1%
+pub const DEFAULT_MAX_VALIDATOR_SHARE: u64 = 100_000; // 100%, no cap
+pub const DEFAULT_MAX_PREFERRED_SHARE: u64 = 5_000; // 5%
+
+/// new fields must have a serde default, so policies stored by older versions can still be read
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq)]
+pub struct ValidatorPolicy {
+    pub max_commission: u64, // 10^-3 percent. 1 = 0.001%
+    /// how far (as a share of the total stake) a validator may drift from its target before
+    /// a rebalance moves stake. Same units as max_commission
+    #[serde(default = "default_drift_tolerance")]
+    pub drift_tolerance: u64,
+    /// DelegationStrategyKind as u8
+    #[serde(default)]
+    pub strategy: u8,
+    /// the largest share of the total stake a single validator may hold. Enforced for every
+    /// strategy, when placing deposits, redistributing stake and redelegating
+    #[serde(default = "default_max_validator_share")]
+    pub max_validator_share: u64,
+    /// the largest share of the total stake depositors can place on a single validator through
+    /// a validator preference
+    #[serde(default = "default_max_preferred_share")]
+    pub max_preferred_share: u64,
+}
+
+impl Default for ValidatorPolicy {
+    fn default() -> Self {
+        Self {
+            max_commission: DEFAULT_MAX_COMMISSION,
+            drift_tolerance: DEFAULT_DRIFT_TOLERANCE,
+            strategy: 0,
+            max_validator_share: DEFAULT_MAX_VALIDATOR_SHARE,
+            max_preferred_share: DEFAULT_MAX_PREFERRED_SHARE,
+        }
+    }
+}
+
+fn default_drift_tolerance() -> u64 {
+    DEFAULT_DRIFT_TOLERANCE
+}
+
+fn default_max_validator_share() -> u64 {
+    DEFAULT_MAX_VALIDATOR_SHARE
+}
+
+fn default_max_preferred_share() -> u64 {
+    DEFAULT_MAX_PREFERRED_SHARE
+}
+
+pub fn set_validator_policy<S: Storage>(storage: &mut S, data: &ValidatorPolicy) -> StdResult<()> {
+    Singleton::new(storage, KEY_VALIDATOR_POLICY).save(data)
+}
+
+/// contracts that were initialized before the policy existed fall back to the default policy
+pub fn read_validator_policy<S: Storage>(storage: &S) -> StdResult<ValidatorPolicy> {
+    Ok(ReadonlySingleton::new(storage, KEY_VALIDATOR_POLICY)
+        .may_load()?
+        .unwrap_or_default())
+}
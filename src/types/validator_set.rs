@@ -1,12 +1,18 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::staking::{undelegate_msg, withdraw_to_self};
+use crate::constants::{FEE_RESOLUTION, MAX_UNBONDING_ENTRIES};
+use crate::staking::withdraw_to_self;
 use crate::types::config::PREFIX_CONFIG;
+use crate::types::delegation_strategy::{
+    add_allocation, split_by_shares, Allocations, DelegationStrategy,
+};
+use crate::types::validator_status::ValidatorStatus;
 use cosmwasm_std::{CosmosMsg, ReadonlyStorage, StdError, StdResult, Storage, Uint128};
 use cosmwasm_storage::{PrefixedStorage, ReadonlyPrefixedStorage};
 use std::cmp::Ordering;
 use std::collections::VecDeque;
+use std::convert::TryFrom;
 
 pub const DEFAULT_WEIGHT: u8 = 10;
 
@@ -17,7 +23,43 @@ pub struct ValidatorResponse {
     pub(crate) address: String,
     pub(crate) staked: Uint128,
     pub(crate) weight: u8,
-    //weight: u8
+    pub(crate) status: ValidatorStatus,
+    /// this validator can't be a redelegation source until this time
+    pub(crate) redelegation_cooldown: u64,
+    /// stake placed here because depositors asked for this validator
+    pub(crate) preferred: Uint128,
+}
+
+/// Everything the Validators query shows about a validator. Shares are in FEE_RESOLUTION units
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+pub struct ValidatorDetails {
+    pub address: String,
+    pub weight: u8,
+    pub status: ValidatorStatus,
+    pub staked: Uint128,
+    pub target_share: u64,
+    pub actual_share: u64,
+    pub pending_rewards: Uint128,
+    pub unbonding: Vec<UnbondingEntry>,
+    /// whether the validator is in the active set of the chain
+    pub on_chain_active: bool,
+    /// None when the validator is not in the active set of the chain
+    pub commission: Option<u64>,
+    pub last_sync: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+pub struct PlannedMove {
+    pub from: String,
+    pub to: String,
+    pub amount: Uint128,
+}
+
+/// An undelegation we sent to a validator that hasn't matured yet
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Clone, JsonSchema)]
+pub struct UnbondingEntry {
+    pub amount: Uint128,
+    pub completion_time: u64,
 }
 
 #[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Clone, JsonSchema)]
@@ -25,7 +67,47 @@ pub struct Validator {
     pub(crate) address: String,
     pub(crate) staked: u128,
     pub(crate) weight: u8,
-    //weight: u8
+    pub(crate) status: u8,
+    pub(crate) redelegation_cooldown: u64,
+    /// the part of `staked` that depositors asked to place on this validator. It is counted on
+    /// top of the weighted target
+    pub(crate) preferred: u128,
+    /// undelegations in flight, pruned as they mature
+    pub(crate) unbonding: Vec<UnbondingEntry>,
+    /// the last time our bookkeeping was checked against the real delegation
+    pub(crate) last_sync: u64,
+}
+
+impl Validator {
+    pub fn is_active(&self) -> bool {
+        let active: u8 = ValidatorStatus::Active.into();
+        self.status == active
+    }
+
+    pub fn is_retiring(&self) -> bool {
+        let retiring: u8 = ValidatorStatus::Retiring.into();
+        self.status == retiring
+    }
+
+    /// the chain rejects redelegations from a validator that still has an immature incoming
+    /// redelegation from us
+    pub fn is_redelegation_blocked(&self, current_time: u64) -> bool {
+        self.redelegation_cooldown > current_time
+    }
+
+    /// the chain refuses an undelegation once MAX_UNBONDING_ENTRIES are in flight
+    pub fn can_undelegate(&self, current_time: u64) -> bool {
+        self.unbonding
+            .iter()
+            .filter(|entry| entry.completion_time > current_time)
+            .count()
+            < MAX_UNBONDING_ENTRIES
+    }
+
+    /// stake can be moved away either by redelegating it, or by undelegating it to be restaked
+    pub fn can_move_stake(&self, current_time: u64) -> bool {
+        !self.is_redelegation_blocked(current_time) || self.can_undelegate(current_time)
+    }
 }
 
 impl PartialOrd for Validator {
@@ -45,9 +127,16 @@ impl Ord for Validator {
 #[derive(Serialize, Debug, Deserialize, Clone, PartialEq, Default, JsonSchema)]
 pub struct ValidatorSet {
     validators: VecDeque<Validator>,
+    /// advanced every time stake is placed - see DelegationStrategy
+    cursor: u64,
 }
 
 impl ValidatorSet {
+    /// rebuilds a set that was stored in an older layout - see legacy_validator_set
+    pub(crate) fn from_parts(validators: VecDeque<Validator>, cursor: u64) -> Self {
+        Self { validators, cursor }
+    }
+
     pub fn to_query_response(&self) -> Vec<ValidatorResponse> {
         self.validators
             .clone()
@@ -56,15 +145,214 @@ impl ValidatorSet {
                 address: v.address,
                 staked: Uint128(v.staked),
                 weight: v.weight,
+                status: ValidatorStatus::try_from(v.status).unwrap_or(ValidatorStatus::Active),
+                redelegation_cooldown: v.redelegation_cooldown,
+                preferred: Uint128(v.preferred),
             })
             .collect()
     }
 
-    pub fn next_to_unbond(&self) -> Option<&Validator> {
+    /// places `to_stake` on the active validators according to `strategy`, and returns how much
+    /// went to each validator. No validator is pushed above `max_share` of the total stake - see
+    /// cap_allocations
+    pub fn delegate(
+        &mut self,
+        strategy: &dyn DelegationStrategy,
+        to_stake: u128,
+        max_share: u64,
+    ) -> StdResult<Allocations> {
+        if self.validators.is_empty() {
+            return Err(StdError::generic_err(
+                "Failed to get validator to stake - validator set is empty",
+            ));
+        }
+
+        let active: Vec<Validator> = self
+            .validators
+            .iter()
+            .filter(|v| v.is_active())
+            .cloned()
+            .collect();
+
+        let allocations = self.cap_allocations(
+            strategy.allocate_stake(&active, to_stake, self.cursor)?,
+            max_share,
+            self.total_staked() + to_stake,
+        );
+        for (address, amount) in allocations.iter() {
+            self.stake_at(address, *amount)?;
+        }
+
+        self.cursor = self.cursor.wrapping_add(1);
+        self.rebalance();
+
+        Ok(allocations)
+    }
+
+    /// places up to `to_stake` on the validator a depositor asked for. The stake placed on a
+    /// validator by preference never grows past `max_share` of `total_after` (the total stake
+    /// once the deposit is placed), and its whole stake never grows past `max_validator_share`.
+    /// Returns how much was placed - the caller delegates the rest
+    pub fn delegate_preferred(
+        &mut self,
+        address: &str,
+        to_stake: u128,
+        max_share: u64,
+        max_validator_share: u64,
+        total_after: u128,
+    ) -> StdResult<u128> {
+        let val = self
+            .validators
+            .iter_mut()
+            .find(|v| v.address == address)
+            .ok_or_else(|| {
+                StdError::generic_err(format!("Validator {} is not in the validator set", address))
+            })?;
+
+        if !val.is_active() {
+            return Err(StdError::generic_err(format!(
+                "Validator {} is not active",
+                address
+            )));
+        }
+
+        let cap = total_after.saturating_mul(max_share as u128) / FEE_RESOLUTION;
+        let validator_cap =
+            total_after.saturating_mul(max_validator_share as u128) / FEE_RESOLUTION;
+        let placed = std::cmp::min(
+            to_stake,
+            std::cmp::min(
+                cap.saturating_sub(val.preferred),
+                validator_cap.saturating_sub(val.staked),
+            ),
+        );
+
+        val.staked += placed;
+        val.preferred += placed;
+
+        self.rebalance();
+
+        Ok(placed)
+    }
+
+    /// makes sure `allocations` don't push any validator above `max_share` of `total_after` (the
+    /// total stake once the allocations are placed). What doesn't fit is split between the active
+    /// validators that still have room, in proportion to that room. If the whole set is full, the
+    /// rest is spread by weight - the cap is too tight for the current set
+    fn cap_allocations(
+        &self,
+        allocations: Allocations,
+        max_share: u64,
+        total_after: u128,
+    ) -> Allocations {
+        let cap = total_after.saturating_mul(max_share as u128) / FEE_RESOLUTION;
+        let room = |capped: &Allocations, address: &str| -> u128 {
+            let staked = self.get(address).map(|v| v.staked).unwrap_or_default();
+            let allocated = capped
+                .iter()
+                .find(|(a, _)| a == address)
+                .map(|(_, amount)| *amount)
+                .unwrap_or_default();
+            cap.saturating_sub(staked + allocated)
+        };
+
+        let mut capped: Allocations = vec![];
+        let mut overflow: u128 = 0;
+
+        for (address, amount) in allocations {
+            let placed = std::cmp::min(amount, room(&capped, &address));
+            if placed > 0 {
+                add_allocation(&mut capped, &address, placed);
+            }
+            overflow += amount - placed;
+        }
+
+        if overflow == 0 {
+            return capped;
+        }
+
+        let rooms: Vec<(String, u128)> = self
+            .validators
+            .iter()
+            .filter(|v| v.is_active())
+            .map(|v| (v.address.clone(), room(&capped, &v.address)))
+            .filter(|(_, room)| *room > 0)
+            .collect();
+        let total_room: u128 = rooms.iter().map(|(_, room)| room).sum();
+
+        let fits = std::cmp::min(overflow, total_room);
+        for (address, amount) in split_by_shares(&rooms, fits).unwrap_or_default() {
+            add_allocation(&mut capped, &address, amount);
+        }
+        overflow -= fits;
+
+        if overflow > 0 {
+            let weights: Vec<(String, u128)> = self
+                .validators
+                .iter()
+                .filter(|v| v.is_active())
+                .map(|v| (v.address.clone(), v.weight as u128))
+                .collect();
+            for (address, amount) in split_by_shares(&weights, overflow).unwrap_or_default() {
+                add_allocation(&mut capped, &address, amount);
+            }
+        }
+
+        capped
+    }
+
+    /// takes `to_unbond` off the validators according to `strategy`, and returns how much to
+    /// undelegate from each validator. Healthy validators are unbonded from first. We only fall
+    /// back to unhealthy validators when nothing is left on the healthy ones, since withdraws
+    /// have to be honoured either way
+    pub fn undelegate(
+        &mut self,
+        strategy: &dyn DelegationStrategy,
+        to_unbond: u128,
+    ) -> StdResult<Allocations> {
         if self.validators.is_empty() {
-            return None;
+            return Err(StdError::generic_err(
+                "Failed to get validator to unbond - validator set is empty",
+            ));
+        }
+
+        let (active, inactive): (Vec<Validator>, Vec<Validator>) =
+            self.validators.iter().cloned().partition(|v| v.is_active());
+
+        let mut allocations = strategy.allocate_unbond(&active, to_unbond, self.cursor)?;
+
+        let allocated: u128 = allocations.iter().map(|(_, amount)| amount).sum();
+        if allocated < to_unbond {
+            let remainder =
+                strategy.allocate_unbond(&inactive, to_unbond - allocated, self.cursor)?;
+            for (address, amount) in remainder {
+                add_allocation(&mut allocations, &address, amount);
+            }
+        }
+
+        let total_before = self.total_staked();
+        for (address, amount) in allocations.iter() {
+            if let Some(val) = self.validators.iter_mut().find(|v| &v.address == address) {
+                val.staked = val.staked.saturating_sub(*amount);
+            }
+        }
+
+        // tokens are fungible, so withdraws shrink every preference by the same ratio
+        let total_after = self.total_staked();
+        for val in self.validators.iter_mut() {
+            val.preferred = if total_before == 0 {
+                0
+            } else {
+                std::cmp::min(
+                    val.preferred.saturating_mul(total_after) / total_before,
+                    val.staked,
+                )
+            };
         }
-        self.validators.front()
+
+        self.rebalance();
+
+        Ok(allocations)
     }
 
     pub fn remove(&mut self, address: &str, force: bool) -> StdResult<Option<Validator>> {
@@ -90,23 +378,66 @@ impl ValidatorSet {
             )));
         }
 
-        Ok(self.validators.remove(pos.unwrap()))
+        // the preferences for it lapse - its stake is spread by weight from here on
+        Ok(self.validators.remove(pos.unwrap()).map(|mut val| {
+            val.preferred = 0;
+            val
+        }))
     }
 
     pub fn total_staked(&self) -> u128 {
         self.validators.iter().map(|val| val.staked).sum()
     }
 
+    pub fn len(&self) -> usize {
+        self.validators.len()
+    }
+
+    pub fn addresses(&self) -> Vec<String> {
+        self.validators.iter().map(|v| v.address.clone()).collect()
+    }
+
     pub fn add(&mut self, address: String, weight: Option<u8>) {
         if self.exists(&address).is_none() {
             self.validators.push_back(Validator {
                 address,
                 staked: 0,
                 weight: weight.unwrap_or(DEFAULT_WEIGHT),
+                status: ValidatorStatus::Active.into(),
+                redelegation_cooldown: 0,
+                preferred: 0,
+                unbonding: vec![],
+                last_sync: 0,
             })
         }
     }
 
+    /// makes `validators` the new set. Validators that are left out become retiring, and stay in
+    /// the set until their stake is moved - see plan_migration
+    pub fn replace(&mut self, validators: Vec<(String, u8)>) -> StdResult<()> {
+        for val in self.validators.iter_mut() {
+            if !validators
+                .iter()
+                .any(|(address, _)| address == &val.address)
+            {
+                val.status = ValidatorStatus::Retiring.into();
+            }
+        }
+
+        for (address, weight) in validators {
+            if self.exists(&address).is_some() {
+                self.change_weight(&address, Some(weight))?;
+                self.set_status(&address, ValidatorStatus::Active)?;
+            } else {
+                self.add(address, Some(weight));
+            }
+        }
+
+        self.rebalance();
+
+        Ok(())
+    }
+
     pub fn change_weight(&mut self, address: &str, weight: Option<u8>) -> StdResult<()> {
         let pos = self.exists(address);
         if pos.is_none() {
@@ -128,30 +459,6 @@ impl ValidatorSet {
         Ok(())
     }
 
-    pub fn unbond(&mut self, to_unbond: u128) -> StdResult<String> {
-        if self.validators.is_empty() {
-            return Err(StdError::generic_err(
-                "Failed to get validator to unbond - validator set is empty",
-            ));
-        }
-
-        let val = self.validators.front_mut().unwrap();
-        val.staked = val.staked.saturating_sub(to_unbond);
-        Ok(val.address.clone())
-    }
-
-    pub fn stake(&mut self, to_stake: u128) -> StdResult<String> {
-        if self.validators.is_empty() {
-            return Err(StdError::generic_err(
-                "Failed to get validator to stake - validator set is empty",
-            ));
-        }
-
-        let val = self.validators.back_mut().unwrap();
-        val.staked += to_stake;
-        Ok(val.address.clone())
-    }
-
     pub fn stake_at(&mut self, address: &str, to_stake: u128) -> StdResult<()> {
         if self.validators.is_empty() {
             return Err(StdError::generic_err(
@@ -161,6 +468,12 @@ impl ValidatorSet {
 
         for val in self.validators.iter_mut() {
             if val.address == address {
+                if !val.is_active() {
+                    return Err(StdError::generic_err(format!(
+                        "Failed to stake - validator {} is not active",
+                        address
+                    )));
+                }
                 val.staked += to_stake;
                 return Ok(());
             }
@@ -171,10 +484,366 @@ impl ValidatorSet {
         ))
     }
 
+    pub fn set_status(&mut self, address: &str, status: ValidatorStatus) -> StdResult<()> {
+        let val = self
+            .validators
+            .iter_mut()
+            .find(|v| v.address == address)
+            .ok_or_else(|| StdError::generic_err(format!("Validator {} doesn't exist", address)))?;
+
+        val.status = status.into();
+
+        Ok(())
+    }
+
+    pub fn mark_redelegation_target(
+        &mut self,
+        address: &str,
+        cooldown_until: u64,
+    ) -> StdResult<()> {
+        let val = self
+            .validators
+            .iter_mut()
+            .find(|v| v.address == address)
+            .ok_or_else(|| StdError::generic_err(format!("Validator {} doesn't exist", address)))?;
+
+        val.redelegation_cooldown = std::cmp::max(val.redelegation_cooldown, cooldown_until);
+
+        Ok(())
+    }
+
+    /// overwrites our bookkeeping for a validator with what is really delegated to it, and
+    /// returns what we had on record before
+    pub fn set_staked(
+        &mut self,
+        address: &str,
+        staked: u128,
+        current_time: u64,
+    ) -> StdResult<u128> {
+        let val = self
+            .validators
+            .iter_mut()
+            .find(|v| v.address == address)
+            .ok_or_else(|| StdError::generic_err(format!("Validator {} doesn't exist", address)))?;
+
+        let previous = val.staked;
+        val.staked = staked;
+        val.preferred = std::cmp::min(val.preferred, staked);
+        val.last_sync = current_time;
+        val.unbonding
+            .retain(|entry| entry.completion_time > current_time);
+
+        Ok(previous)
+    }
+
+    /// zeroes the stake of a validator and returns how much was staked with it. Used when moving
+    /// the entire stake away from a validator without removing it from the set
+    pub fn take_stake(&mut self, address: &str) -> StdResult<u128> {
+        let val = self
+            .validators
+            .iter_mut()
+            .find(|v| v.address == address)
+            .ok_or_else(|| StdError::generic_err(format!("Validator {} doesn't exist", address)))?;
+
+        let staked = val.staked;
+        val.staked = 0;
+        val.preferred = 0;
+
+        Ok(staked)
+    }
+
+    /// remembers an undelegation sent to `address`, and forgets the ones that already matured.
+    /// Validators that already left the set are ignored
+    pub fn record_unbonding(
+        &mut self,
+        address: &str,
+        amount: u128,
+        current_time: u64,
+        unbonding_time: u64,
+    ) {
+        if let Some(val) = self.validators.iter_mut().find(|v| v.address == address) {
+            val.unbonding
+                .retain(|entry| entry.completion_time > current_time);
+            val.unbonding.push(UnbondingEntry {
+                amount: Uint128(amount),
+                completion_time: current_time + unbonding_time,
+            });
+        }
+    }
+
+    pub fn unhealthy(&self) -> Vec<Validator> {
+        self.validators
+            .iter()
+            .filter(|v| !v.is_active())
+            .cloned()
+            .collect()
+    }
+
+    pub fn get(&self, address: &str) -> Option<&Validator> {
+        self.validators.iter().find(|v| v.address == address)
+    }
+
     pub fn exists(&self, address: &str) -> Option<usize> {
         self.validators.iter().position(|v| v.address == address)
     }
 
+    /// how much each validator should have staked if the current stake was split exactly
+    /// according to weight, on top of the stake depositors placed on it by preference.
+    /// Unhealthy validators have a target of 0, and no target is above `max_share` of the total
+    /// stake - the excess goes to the other active validators by weight
+    pub fn targets(&self, max_share: u64) -> Vec<(String, u128)> {
+        let total_preferred: u128 = self
+            .validators
+            .iter()
+            .filter(|v| v.is_active())
+            .map(|v| v.preferred)
+            .sum();
+        let total_staked = self.total_staked().saturating_sub(total_preferred);
+        let total_weight: u128 = self
+            .validators
+            .iter()
+            .filter(|v| v.is_active())
+            .map(|v| v.weight as u128)
+            .sum();
+
+        let targets: Vec<(String, u128)> = self
+            .validators
+            .iter()
+            .map(|v| {
+                let target = if v.is_active() && total_weight > 0 {
+                    v.preferred + total_staked.saturating_mul(v.weight as u128) / total_weight
+                } else {
+                    0
+                };
+                (v.address.clone(), target)
+            })
+            .collect();
+
+        self.cap_targets(targets, max_share)
+    }
+
+    /// moves whatever is above the cap to the active validators that are below it, by weight,
+    /// until no target is above the cap or every active validator is at the cap
+    fn cap_targets(&self, mut targets: Vec<(String, u128)>, max_share: u64) -> Vec<(String, u128)> {
+        let cap = self.total_staked().saturating_mul(max_share as u128) / FEE_RESOLUTION;
+
+        // every round caps at least one more validator, so this ends within len() rounds
+        for _ in 0..self.validators.len() {
+            let mut excess: u128 = 0;
+            for (_, target) in targets.iter_mut() {
+                if *target > cap {
+                    excess += *target - cap;
+                    *target = cap;
+                }
+            }
+            if excess == 0 {
+                break;
+            }
+
+            let below_cap: Vec<(String, u128)> = targets
+                .iter()
+                .filter(|(_, target)| *target < cap)
+                .filter_map(|(address, _)| {
+                    let validator = self.get(address)?;
+                    if validator.is_active() {
+                        Some((address.clone(), validator.weight as u128))
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+
+            let spread = match split_by_shares(&below_cap, excess) {
+                Some(spread) => spread,
+                // the cap is too tight for the current set
+                None => break,
+            };
+            for (address, amount) in spread {
+                if let Some(entry) = targets.iter_mut().find(|(a, _)| a == &address) {
+                    entry.1 += amount;
+                }
+            }
+        }
+
+        targets
+    }
+
+    /// plans at most `max_moves` redelegations that move the set towards its target weights.
+    /// Validators that are within `tolerance` (a share of the total stake, in FEE_RESOLUTION
+    /// units) of their target are left alone, and so are sources that are blocked by an
+    /// incoming redelegation - they will be picked up by a later rebalance
+    pub fn plan_rebalance(
+        &self,
+        max_moves: u32,
+        tolerance: u64,
+        max_share: u64,
+        current_time: u64,
+    ) -> Vec<PlannedMove> {
+        let threshold = self.total_staked().saturating_mul(tolerance as u128) / FEE_RESOLUTION;
+
+        let mut surpluses: Vec<(String, u128)> = vec![];
+        let mut deficits: Vec<(String, u128)> = vec![];
+
+        for (address, target) in self.targets(max_share) {
+            let validator = match self.get(&address) {
+                Some(validator) => validator,
+                None => continue,
+            };
+            let staked = validator.staked;
+            if staked > target.saturating_add(threshold) {
+                if validator.is_redelegation_blocked(current_time) {
+                    continue;
+                }
+                surpluses.push((address, staked - target));
+            } else if target > staked.saturating_add(threshold) {
+                deficits.push((address, target - staked));
+            }
+        }
+
+        // largest imbalances first, so a small number of moves does the most work
+        surpluses.sort_by(|a, b| b.1.cmp(&a.1));
+        deficits.sort_by(|a, b| b.1.cmp(&a.1));
+
+        let mut moves: Vec<PlannedMove> = vec![];
+        let (mut i, mut j) = (0, 0);
+
+        while i < surpluses.len() && j < deficits.len() && moves.len() < max_moves as usize {
+            let amount = std::cmp::min(surpluses[i].1, deficits[j].1);
+
+            moves.push(PlannedMove {
+                from: surpluses[i].0.clone(),
+                to: deficits[j].0.clone(),
+                amount: Uint128(amount),
+            });
+
+            surpluses[i].1 -= amount;
+            deficits[j].1 -= amount;
+
+            if surpluses[i].1 <= threshold {
+                i += 1;
+            }
+            if deficits[j].1 <= threshold {
+                j += 1;
+            }
+        }
+
+        moves
+    }
+
+    /// plans at most `max_moves` redelegations that empty the retiring validators into the
+    /// active ones, filling the active validators that are furthest below their target first.
+    /// Retiring validators that are blocked by an incoming redelegation are skipped until their
+    /// cooldown is over
+    pub fn plan_migration(
+        &self,
+        max_moves: u32,
+        max_share: u64,
+        current_time: u64,
+    ) -> Vec<PlannedMove> {
+        let mut deficits: Vec<(String, u128)> = self
+            .targets(max_share)
+            .into_iter()
+            .filter_map(|(address, target)| {
+                let validator = self.get(&address)?;
+                if validator.is_active() && target > validator.staked {
+                    Some((address, target - validator.staked))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        deficits.sort_by(|a, b| b.1.cmp(&a.1));
+
+        // rounding can leave a few uscrt that no deficit accounts for
+        let fallback = self.validators.iter().filter(|v| v.is_active()).min();
+
+        let mut moves: Vec<PlannedMove> = vec![];
+
+        for source in self.validators.iter().filter(|v| v.is_retiring()) {
+            if source.is_redelegation_blocked(current_time) {
+                continue;
+            }
+
+            let mut remaining = source.staked;
+            for deficit in deficits.iter_mut() {
+                if remaining == 0 || moves.len() >= max_moves as usize {
+                    break;
+                }
+                let amount = std::cmp::min(remaining, deficit.1);
+                if amount == 0 {
+                    continue;
+                }
+
+                moves.push(PlannedMove {
+                    from: source.address.clone(),
+                    to: deficit.0.clone(),
+                    amount: Uint128(amount),
+                });
+                deficit.1 -= amount;
+                remaining -= amount;
+            }
+
+            if remaining > 0 && moves.len() < max_moves as usize {
+                if let Some(dest) = fallback {
+                    moves.push(PlannedMove {
+                        from: source.address.clone(),
+                        to: dest.address.clone(),
+                        amount: Uint128(remaining),
+                    });
+                }
+            }
+        }
+
+        moves
+    }
+
+    /// removes the retiring validators that have no stake left, and returns their addresses
+    pub fn remove_retired(&mut self) -> Vec<String> {
+        let retired: Vec<String> = self
+            .validators
+            .iter()
+            .filter(|v| v.is_retiring() && v.staked == 0)
+            .map(|v| v.address.clone())
+            .collect();
+
+        self.validators
+            .retain(|v| !(v.is_retiring() && v.staked == 0));
+
+        retired
+    }
+
+    pub fn retiring(&self) -> Vec<Validator> {
+        self.validators
+            .iter()
+            .filter(|v| v.is_retiring())
+            .cloned()
+            .collect()
+    }
+
+    /// applies planned redelegations to our bookkeeping. `cooldown_until` is when the
+    /// redelegations mature, and the destinations can be redelegated from again
+    pub fn apply_moves(&mut self, moves: &[PlannedMove], cooldown_until: u64) -> StdResult<()> {
+        for planned in moves {
+            let from = self
+                .validators
+                .iter_mut()
+                .find(|v| v.address == planned.from)
+                .ok_or_else(|| {
+                    StdError::generic_err(format!("Validator {} doesn't exist", planned.from))
+                })?;
+            from.staked = from.staked.saturating_sub(planned.amount.u128());
+            // a preference is for this validator - it doesn't follow the stake that leaves
+            from.preferred = std::cmp::min(from.preferred, from.staked);
+
+            self.stake_at(&planned.to, planned.amount.u128())?;
+            self.mark_redelegation_target(&planned.to, cooldown_until)?;
+        }
+
+        self.rebalance();
+
+        Ok(())
+    }
+
     // call this after every stake or unbond call
     pub fn rebalance(&mut self) {
         if self.validators.len() < 2 {
@@ -200,31 +869,45 @@ impl ValidatorSet {
         }
     }
 
-    pub fn unbond_all(&self) -> Vec<CosmosMsg> {
-        self.validators
+    /// Undelegates the whole stake of up to `limit` validators. Validators that already have
+    /// MAX_UNBONDING_ENTRIES undelegations in flight are skipped - the chain would refuse another
+    /// one - and picked up by a later batch. Returns what was unbonded from each validator
+    pub fn unbond_batch(
+        &mut self,
+        limit: usize,
+        current_time: u64,
+        unbonding_time: u64,
+    ) -> Vec<(String, u128)> {
+        let to_unbond: Vec<(String, u128)> = self
+            .validators
             .iter()
-            .filter(|&val| val.staked > 0)
-            .map(|val| undelegate_msg(&val.address, val.staked))
-            .collect()
-    }
+            .filter(|val| val.staked > 0)
+            .filter(|val| val.can_undelegate(current_time))
+            .take(limit)
+            .map(|val| (val.address.clone(), val.staked))
+            .collect();
 
-    pub fn zero(&mut self) {
-        if self.validators.is_empty() {
-            return;
+        for (address, staked) in to_unbond.iter() {
+            self.record_unbonding(address, *staked, current_time, unbonding_time);
+            if let Some(val) = self.validators.iter_mut().find(|v| &v.address == address) {
+                val.staked = 0;
+                val.preferred = 0;
+            }
         }
 
-        for val in self.validators.iter_mut() {
-            val.staked = 0;
-        }
+        to_unbond
     }
 }
 
 /// todo: validator address is a String till we test with HumanAddr and see that secretval addresses are working
 pub fn get_validator_set<S: Storage>(store: &S) -> StdResult<ValidatorSet> {
     let config_store = ReadonlyPrefixedStorage::new(PREFIX_CONFIG, store);
-    let x = config_store.get(KEY_VALIDATOR_SET).unwrap();
-    let record: ValidatorSet = bincode2::deserialize(&x)
-        .map_err(|_| StdError::generic_err("Error unpacking validator set"))?;
+    let x = config_store
+        .get(KEY_VALIDATOR_SET)
+        .ok_or_else(|| StdError::generic_err("There is no stored validator set"))?;
+    let record: ValidatorSet = bincode2::deserialize(&x).map_err(|_| {
+        StdError::generic_err("Error unpacking validator set - it may need to be migrated")
+    })?;
     Ok(record)
 }
 
@@ -240,3 +923,173 @@ pub fn set_validator_set<S: Storage>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::constants::MAX_REDELEGATIONS_PER_TX;
+    use crate::types::delegation_strategy::Proportional;
+
+    fn validator(address: &str, staked: u128, weight: u8) -> Validator {
+        Validator {
+            address: address.to_string(),
+            staked,
+            weight,
+            status: ValidatorStatus::Active.into(),
+            redelegation_cooldown: 0,
+            preferred: 0,
+            unbonding: vec![],
+            last_sync: 0,
+        }
+    }
+
+    fn set(validators: Vec<Validator>) -> ValidatorSet {
+        ValidatorSet::from_parts(VecDeque::from(validators), 0)
+    }
+
+    fn target_of(targets: &[(String, u128)], address: &str) -> u128 {
+        targets
+            .iter()
+            .find(|(a, _)| a == address)
+            .map(|(_, target)| *target)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_targets_by_weight_and_preference() {
+        let mut a = validator("a", 600, 10);
+        a.preferred = 100;
+        let mut jailed = validator("jailed", 100, 10);
+        jailed.status = ValidatorStatus::Jailed.into();
+        let set = set(vec![a, validator("b", 300, 10), jailed]);
+
+        // 100 preferred on top, the other 900 by weight between the active validators
+        let targets = set.targets(FEE_RESOLUTION as u64);
+        assert_eq!(target_of(&targets, "a"), 550);
+        assert_eq!(target_of(&targets, "b"), 450);
+        assert_eq!(target_of(&targets, "jailed"), 0);
+    }
+
+    #[test]
+    fn test_cap_targets() {
+        let set = set(vec![
+            validator("a", 1_000, 30),
+            validator("b", 0, 10),
+            validator("c", 0, 10),
+        ]);
+
+        // a would get 600 of 1000, the 100 above the cap go to b and c by weight
+        let targets = set.targets(50_000);
+        assert_eq!(target_of(&targets, "a"), 500);
+        assert_eq!(target_of(&targets, "b"), 250);
+        assert_eq!(target_of(&targets, "c"), 250);
+    }
+
+    #[test]
+    fn test_cap_with_a_single_validator() {
+        let set = set(vec![validator("a", 1_000, 10)]);
+
+        // there's nowhere for the excess to go - the target stays at the cap
+        assert_eq!(set.targets(50_000), vec![("a".to_string(), 500)]);
+        // and there's nowhere to move the stake to either
+        assert!(set.plan_rebalance(5, 0, 50_000, 0).is_empty());
+    }
+
+    #[test]
+    fn test_plan_rebalance_threshold() {
+        let set = set(vec![validator("a", 540, 10), validator("b", 460, 10)]);
+
+        // 40 off target, within 5% of the total stake
+        assert!(set
+            .plan_rebalance(5, 5_000, FEE_RESOLUTION as u64, 0)
+            .is_empty());
+
+        // but not within 1%
+        assert_eq!(
+            set.plan_rebalance(5, 1_000, FEE_RESOLUTION as u64, 0),
+            vec![PlannedMove {
+                from: "a".to_string(),
+                to: "b".to_string(),
+                amount: Uint128(40),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_plan_rebalance_is_truncated() {
+        let mut validators = vec![];
+        for i in 0..6 {
+            validators.push(validator(&format!("over{}", i), 200, 10));
+            validators.push(validator(&format!("under{}", i), 0, 10));
+        }
+        let set = set(validators);
+
+        let moves = set.plan_rebalance(MAX_REDELEGATIONS_PER_TX, 0, FEE_RESOLUTION as u64, 0);
+        assert_eq!(moves.len(), MAX_REDELEGATIONS_PER_TX as usize);
+        assert!(moves.iter().all(|m| m.amount == Uint128(100)));
+    }
+
+    #[test]
+    fn test_plan_rebalance_skips_blocked_sources() {
+        let mut blocked = validator("blocked", 600, 10);
+        blocked.redelegation_cooldown = 1_000;
+        let set = set(vec![
+            blocked,
+            validator("b", 400, 10),
+            validator("c", 200, 10),
+        ]);
+
+        // targets are 400 each - only b has nothing to give, and blocked can't until 1_000
+        assert!(set
+            .plan_rebalance(5, 0, FEE_RESOLUTION as u64, 999)
+            .is_empty());
+        assert_eq!(
+            set.plan_rebalance(5, 0, FEE_RESOLUTION as u64, 1_000),
+            vec![PlannedMove {
+                from: "blocked".to_string(),
+                to: "c".to_string(),
+                amount: Uint128(200),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_cap_allocations() {
+        let set = set(vec![
+            validator("a", 400, 10),
+            validator("b", 0, 10),
+            validator("c", 0, 10),
+        ]);
+
+        // 700 in total afterwards, so the cap is 350 and a is already above it
+        let capped = set.cap_allocations(vec![("a".to_string(), 300)], 50_000, 700);
+        assert_eq!(capped, vec![("b".to_string(), 150), ("c".to_string(), 150)]);
+    }
+
+    #[test]
+    fn test_cap_allocations_when_the_set_is_full() {
+        let set = set(vec![validator("a", 400, 10)]);
+
+        // nobody has room - the deposit still has to be placed
+        let capped = set.cap_allocations(vec![("a".to_string(), 100)], 50_000, 500);
+        assert_eq!(capped, vec![("a".to_string(), 100)]);
+    }
+
+    #[test]
+    fn test_undelegate_scales_preferences() {
+        let mut a = validator("a", 600, 10);
+        a.preferred = 300;
+        let mut set = set(vec![a, validator("b", 400, 10)]);
+
+        let allocations = set.undelegate(&Proportional, 500).unwrap();
+        assert_eq!(
+            allocations,
+            vec![("a".to_string(), 300), ("b".to_string(), 200)]
+        );
+
+        // half the stake is gone, so is half of every preference
+        assert_eq!(set.get("a").unwrap().staked, 300);
+        assert_eq!(set.get("a").unwrap().preferred, 150);
+    }
+}
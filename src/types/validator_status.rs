@@ -0,0 +1,46 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::StdError;
+use std::convert::TryFrom;
+
+/// Jailed covers every validator that dropped out of the active set while we still have a
+/// delegation with it - the querier doesn't let us tell apart jailing from simply losing voting
+/// power. Removed means the chain no longer knows about our delegation at all. Retiring
+/// validators were left out of a validator set replacement, and leave the set once their stake
+/// has been moved
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidatorStatus {
+    Active,
+    Jailed,
+    Removed,
+    Retiring,
+}
+
+impl TryFrom<u8> for ValidatorStatus {
+    type Error = StdError;
+
+    fn try_from(other: u8) -> Result<Self, Self::Error> {
+        match other {
+            0 => Ok(Self::Active),
+            1 => Ok(Self::Jailed),
+            2 => Ok(Self::Removed),
+            3 => Ok(Self::Retiring),
+            _ => Err(StdError::generic_err(
+                "Failed to convert ValidatorStatus enum",
+            )),
+        }
+    }
+}
+
+impl Into<u8> for ValidatorStatus {
+    fn into(self) -> u8 {
+        match self {
+            Self::Active => 0u8,
+            Self::Jailed => 1u8,
+            Self::Removed => 2u8,
+            Self::Retiring => 3u8,
+        }
+    }
+}
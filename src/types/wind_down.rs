@@ -0,0 +1,70 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdResult, Storage, Uint128};
+use cosmwasm_storage::{ReadonlySingleton, Singleton};
+
+pub static KEY_WIND_DOWN: &[u8] = b"wind_down";
+
+/// SCRT we expect back from an undelegation. `validator` is None for stake that was already
+/// unbonding to be restaked when the wind-down started
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct ExpectedArrival {
+    pub validator: Option<String>,
+    pub amount: Uint128,
+    pub available_time: u64,
+}
+
+/// The emergency wind-down, started by KillSwitchUnbond. The snapshot is taken when it starts.
+/// Stake is unbonded in batches, and once everything arrived the remaining balance is paid out
+/// to the token holders pro rata. Withdraws that were already queued are paid first
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct WindDown {
+    pub started_at: u64,
+    pub height: u64,
+    /// token supply at the snapshot
+    pub supply: Uint128,
+    /// net assets at the snapshot - what the token holders share once withdraws open
+    #[serde(default)]
+    pub pool: Uint128,
+    /// what was delegated at the snapshot
+    pub delegated: Uint128,
+    /// queued withdraws at the snapshot, including the window that was still open
+    pub claims: Uint128,
+    pub unbonded: Uint128,
+    pub arrivals: Vec<ExpectedArrival>,
+    pub paid_out: Uint128,
+    pub tokens_redeemed: Uint128,
+}
+
+impl WindDown {
+    pub fn expect(&mut self, validator: Option<String>, amount: u128, available_time: u64) {
+        self.arrivals.push(ExpectedArrival {
+            validator,
+            amount: Uint128(amount),
+            available_time,
+        });
+    }
+
+    /// when the last expected undelegation finishes
+    pub fn last_arrival(&self) -> u64 {
+        self.arrivals
+            .iter()
+            .map(|a| a.available_time)
+            .max()
+            .unwrap_or(self.started_at)
+    }
+
+    pub fn record_payout(&mut self, tokens: u128, scrt: u128) {
+        self.tokens_redeemed = Uint128(self.tokens_redeemed.u128() + tokens);
+        self.paid_out = Uint128(self.paid_out.u128() + scrt);
+    }
+}
+
+pub fn set_wind_down<S: Storage>(storage: &mut S, data: &WindDown) -> StdResult<()> {
+    Singleton::new(storage, KEY_WIND_DOWN).save(data)
+}
+
+pub fn read_wind_down<S: Storage>(storage: &S) -> StdResult<Option<WindDown>> {
+    ReadonlySingleton::new(storage, KEY_WIND_DOWN).may_load()
+}
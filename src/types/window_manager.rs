@@ -118,9 +118,12 @@ impl WindowManager {
 
 pub fn get_window_manager<S: ReadonlyStorage>(store: &S) -> StdResult<WindowManager> {
     let config_store = ReadonlyPrefixedStorage::new(PREFIX_WINDOW_MANANGER, store);
-    let x = config_store.get(&PREFIX_WINDOW_MANANGER).unwrap();
-    let record: WindowManager = bincode2::deserialize(&x)
-        .map_err(|_| StdError::generic_err("Error getting window manager"))?;
+    let x = config_store
+        .get(&PREFIX_WINDOW_MANANGER)
+        .ok_or_else(|| StdError::generic_err("There is no stored window manager"))?;
+    let record: WindowManager = bincode2::deserialize(&x).map_err(|_| {
+        StdError::generic_err("Error getting window manager - it may need to be migrated")
+    })?;
     Ok(record)
 }
 
@@ -0,0 +1,76 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdResult, Storage, Uint128};
+use cosmwasm_storage::{ReadonlySingleton, Singleton};
+
+pub static KEY_YIELD_LEDGER: &[u8] = b"yield_ledger";
+
+/// we keep about a year and a half of 3 day windows - plenty for any APR lookback
+pub const MAX_RATE_SNAPSHOTS: usize = 180;
+
+/// The exchange rate at the moment a withdraw window closed
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct RateSnapshot {
+    pub window: u64,
+    pub time: u64,
+    /// SCRT per token
+    pub rate: String,
+}
+
+/// Running totals since the ledger was introduced, all in uscrt
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, Default)]
+pub struct YieldLedger {
+    #[serde(default)]
+    pub rewards_harvested: Uint128,
+    /// dev fees and compound bounties
+    #[serde(default)]
+    pub fees_taken: Uint128,
+    #[serde(default)]
+    pub principal_deposited: Uint128,
+    #[serde(default)]
+    pub principal_withdrawn: Uint128,
+    #[serde(default)]
+    pub snapshots: Vec<RateSnapshot>,
+}
+
+impl YieldLedger {
+    pub fn snapshot(&mut self, window: u64, time: u64, rate: String) {
+        self.snapshots.push(RateSnapshot { window, time, rate });
+
+        if self.snapshots.len() > MAX_RATE_SNAPSHOTS {
+            let excess = self.snapshots.len() - MAX_RATE_SNAPSHOTS;
+            self.snapshots.drain(..excess);
+        }
+    }
+}
+
+pub fn set_yield_ledger<S: Storage>(storage: &mut S, data: &YieldLedger) -> StdResult<()> {
+    Singleton::new(storage, KEY_YIELD_LEDGER).save(data)
+}
+
+pub fn read_yield_ledger<S: Storage>(storage: &S) -> StdResult<YieldLedger> {
+    Ok(ReadonlySingleton::new(storage, KEY_YIELD_LEDGER)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+/// adds to the running totals. Amounts are in uscrt
+pub fn record_flows<S: Storage>(
+    storage: &mut S,
+    rewards: u128,
+    fees: u128,
+    deposited: u128,
+    withdrawn: u128,
+) -> StdResult<()> {
+    let mut ledger = read_yield_ledger(storage)?;
+
+    ledger.rewards_harvested = Uint128(ledger.rewards_harvested.u128().saturating_add(rewards));
+    ledger.fees_taken = Uint128(ledger.fees_taken.u128().saturating_add(fees));
+    ledger.principal_deposited =
+        Uint128(ledger.principal_deposited.u128().saturating_add(deposited));
+    ledger.principal_withdrawn =
+        Uint128(ledger.principal_withdrawn.u128().saturating_add(withdrawn));
+
+    set_yield_ledger(storage, &ledger)
+}
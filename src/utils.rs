@@ -6,6 +6,7 @@ use cosmwasm_std::{Api, CosmosMsg, Env, Extern, HumanAddr, Querier, StdError, St
 use crate::claim::claim_multiple;
 use crate::constants::AMOUNT_OF_SHARED_WITHDRAWS;
 use crate::types::config::Config;
+use crate::types::pause::read_pause_flags;
 use crate::types::shared_withdraw_config::SharedWithdrawConfig;
 
 // Converts 16 bytes value into u128
@@ -58,6 +59,11 @@ pub fn perform_helper_claims<S: Storage, A: Api, Q: Querier>(
     messages: &mut Vec<CosmosMsg>,
 ) -> StdResult<()> {
     let withdraw_config = SharedWithdrawConfig::try_from(constants.shared_withdrawals)?;
+    // while claims are paused, deposits and withdraws just don't help out
+    if read_pause_flags(&deps.storage)?.claims {
+        return Ok(());
+    }
+
     if withdraw_config == SharedWithdrawConfig::Withdraws
         || withdraw_config == SharedWithdrawConfig::All
     {
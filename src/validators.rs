@@ -0,0 +1,542 @@
+use std::convert::TryFrom;
+
+use cosmwasm_std::{
+    debug_print, log, Api, CosmosMsg, Decimal, Env, Extern, HandleResponse, HumanAddr,
+    LogAttribute, Querier, StdError, StdResult, Storage, Uint128,
+};
+
+use crate::constants::{FEE_RESOLUTION, MAX_REDELEGATIONS_PER_TX, MAX_UNBONDING_ENTRIES};
+use crate::pause::ensure_not_paused;
+use crate::staking::{get_delegations, redelegate_msg, stake_msg, undelegate_msg};
+use crate::types::config::{read_config, Config};
+use crate::types::delegation_strategy::strategy_from_policy;
+use crate::types::insurance::{read_insurance_fund, set_insurance_fund};
+use crate::types::killswitch::KillSwitch;
+use crate::types::pending_restake::{
+    read_pending_restake, set_pending_restake, take_matured_restake,
+};
+use crate::types::slashing::{read_slash_history, set_slash_history, SLASH_TOLERANCE};
+use crate::types::validator_performance::{
+    checkpoint_performance, record_commission, record_slash,
+};
+use crate::types::validator_policy::read_validator_policy;
+use crate::types::validator_set::{get_validator_set, set_validator_set, Validator, ValidatorSet};
+use crate::types::validator_status::ValidatorStatus;
+
+/// Permissionless maintenance of the validator set. Anyone can trigger these, since they only
+/// ever move stake according to the rules set by the admin
+pub fn enforce_validator_policy<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> StdResult<HandleResponse> {
+    let config = read_config(&deps.storage)?;
+    ensure_not_frozen(&config)?;
+    ensure_not_paused(&deps.storage, "Validator operations", |paused| {
+        paused.validator_ops
+    })?;
+
+    let policy = read_validator_policy(&deps.storage)?;
+    let max_commission = Decimal::from_ratio(policy.max_commission as u128, FEE_RESOLUTION);
+
+    let chain_validators = deps.querier.query_validators()?;
+    let mut validator_set = get_validator_set(&deps.storage)?;
+
+    for validator in chain_validators.iter() {
+        if validator_set.exists(&validator.address.0).is_some() {
+            record_commission(
+                &mut deps.storage,
+                &validator.address.0,
+                (Uint128(FEE_RESOLUTION) * validator.commission).u128() as u64,
+                env.block.time,
+            )?;
+        }
+    }
+
+    let offenders: Vec<(String, Decimal)> = validator_set
+        .addresses()
+        .into_iter()
+        .filter_map(|address| {
+            chain_validators
+                .iter()
+                .find(|v| v.address.0 == address)
+                .filter(|v| v.commission > max_commission)
+                .map(|v| (address, v.commission))
+        })
+        .collect();
+
+    if offenders.len() >= validator_set.len() {
+        return Err(StdError::generic_err(
+            "Cannot evict every validator in the set - add a compliant validator first",
+        ));
+    }
+
+    let mut logs: Vec<LogAttribute> = vec![
+        log("action", "enforce_validator_policy"),
+        log("account", env.message.sender.as_str()),
+    ];
+
+    // remove all the offenders first, so that their stake never gets redistributed to
+    // another validator that is about to be evicted
+    let mut removed: Vec<Validator> = vec![];
+    for (address, commission) in offenders {
+        debug_print(format!(
+            "evicting validator {} with commission {}",
+            address, commission
+        ));
+        if let Some(validator) = validator_set.remove(&address, true)? {
+            removed.push(validator);
+        }
+        logs.push(log("evicted", &address));
+        logs.push(log("commission", commission));
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for validator in removed {
+        messages.extend(redistribute_stake(
+            &mut deps.storage,
+            &mut validator_set,
+            &validator,
+            validator.staked,
+            env.block.time,
+            config.unbonding_time,
+        )?);
+    }
+
+    checkpoint_performance(&mut deps.storage, &validator_set, env.block.time)?;
+    set_validator_set(&mut deps.storage, &validator_set)?;
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+/// Refreshes the on-chain status of every validator in the set, and moves our stake away from
+/// the ones that are no longer in the active set. Stake is never written off here - if a
+/// delegation is missing, the validator is only marked, and SyncDelegations records any loss
+pub fn evacuate_validators<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> StdResult<HandleResponse> {
+    let config = read_config(&deps.storage)?;
+    ensure_not_frozen(&config)?;
+    ensure_not_paused(&deps.storage, "Validator operations", |paused| {
+        paused.validator_ops
+    })?;
+
+    let chain_validators = deps.querier.query_validators()?;
+    let mut validator_set = get_validator_set(&deps.storage)?;
+
+    let mut logs: Vec<LogAttribute> = vec![
+        log("action", "evacuate_validators"),
+        log("account", env.message.sender.as_str()),
+    ];
+
+    // validators whose delegation is gone although we think we have stake there. Whether that
+    // stake was lost is for SyncDelegations to confirm - until then it's left where it is
+    let mut missing_delegation: Vec<String> = vec![];
+
+    for address in validator_set.addresses() {
+        let status = if chain_validators.iter().any(|v| v.address.0 == address) {
+            ValidatorStatus::Active
+        } else {
+            let has_delegation = deps
+                .querier
+                .query_delegation(&env.contract.address, &HumanAddr(address.clone()))?
+                .is_some();
+            let staked = validator_set
+                .get(&address)
+                .map(|v| v.staked > 0)
+                .unwrap_or_default();
+
+            if has_delegation {
+                ValidatorStatus::Jailed
+            } else if staked {
+                logs.push(log("missing_delegation", &address));
+                missing_delegation.push(address.clone());
+                ValidatorStatus::Jailed
+            } else {
+                ValidatorStatus::Removed
+            }
+        };
+
+        if status != ValidatorStatus::Active {
+            logs.push(log("unhealthy", &address));
+            logs.push(log("status", format!("{:?}", status)));
+        } else if validator_set
+            .get(&address)
+            .map(|v| v.is_retiring())
+            .unwrap_or_default()
+        {
+            // still healthy - the validator set migration takes care of it
+            continue;
+        }
+
+        validator_set.set_status(&address, status)?;
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+
+    if validator_set.len() == validator_set.unhealthy().len() {
+        // nowhere to move the stake to - just remember the statuses so deposits stop flowing in
+        logs.push(log("evacuation", "skipped - no active validators"));
+    } else {
+        let removed_status: u8 = ValidatorStatus::Removed.into();
+
+        for validator in validator_set.unhealthy() {
+            if validator.is_retiring() || missing_delegation.contains(&validator.address) {
+                continue;
+            }
+
+            if validator.status == removed_status {
+                // left the chain with nothing delegated - there is nothing to move
+                if validator.staked == 0 {
+                    validator_set.remove(&validator.address, true)?;
+                    logs.push(log("removed", &validator.address));
+                }
+                continue;
+            }
+
+            if !validator.can_move_stake(env.block.time) {
+                logs.push(log("blocked", &validator.address));
+                continue;
+            }
+
+            let to_move = validator_set.take_stake(&validator.address)?;
+            if to_move == 0 {
+                continue;
+            }
+
+            messages.extend(redistribute_stake(
+                &mut deps.storage,
+                &mut validator_set,
+                &validator,
+                to_move,
+                env.block.time,
+                config.unbonding_time,
+            )?);
+            logs.push(log("evacuated", &validator.address));
+            logs.push(log("amount", to_move));
+        }
+    }
+
+    checkpoint_performance(&mut deps.storage, &validator_set, env.block.time)?;
+    set_validator_set(&mut deps.storage, &validator_set)?;
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+/// Moves stake between validators until every validator is within the drift tolerance of its
+/// target weight. Bounded by `max_moves`, so large imbalances may take several calls
+pub fn rebalance_validators<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    max_moves: Option<u32>,
+) -> StdResult<HandleResponse> {
+    let config = read_config(&deps.storage)?;
+    ensure_not_frozen(&config)?;
+    ensure_not_paused(&deps.storage, "Validator operations", |paused| {
+        paused.validator_ops
+    })?;
+
+    let policy = read_validator_policy(&deps.storage)?;
+    let mut validator_set = get_validator_set(&deps.storage)?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+
+    // anything that was undelegated because a redelegation was blocked goes back to work first
+    let matured = take_matured_restake(&mut deps.storage, env.block.time)?;
+    if matured > 0 {
+        let strategy = strategy_from_policy(&policy)?;
+        for (validator, amount) in
+            validator_set.delegate(strategy.as_ref(), matured, policy.max_validator_share)?
+        {
+            messages.push(stake_msg(&validator, amount));
+        }
+    }
+
+    let moves = validator_set.plan_rebalance(
+        std::cmp::min(
+            max_moves.unwrap_or(MAX_REDELEGATIONS_PER_TX),
+            MAX_REDELEGATIONS_PER_TX,
+        ),
+        policy.drift_tolerance,
+        policy.max_validator_share,
+        env.block.time,
+    );
+
+    validator_set.apply_moves(&moves, env.block.time + config.unbonding_time)?;
+    checkpoint_performance(&mut deps.storage, &validator_set, env.block.time)?;
+    set_validator_set(&mut deps.storage, &validator_set)?;
+
+    let mut logs: Vec<LogAttribute> = vec![
+        log("action", "rebalance"),
+        log("account", env.message.sender.as_str()),
+        log("restaked", matured),
+        log("moves", moves.len()),
+    ];
+
+    for planned in moves {
+        messages.push(redelegate_msg(
+            &planned.from,
+            &planned.to,
+            planned.amount.u128(),
+        ));
+        logs.push(log(
+            "redelegate",
+            format!("{} -> {}: {}", planned.from, planned.to, planned.amount),
+        ));
+    }
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+/// Re-reads what is really delegated to each validator in the set, and corrects our bookkeeping.
+/// Anything missing beyond rounding is recorded as a slash - the exchange rate is calculated from our bookkeeping,
+/// so until this runs a slash is silently paid by whoever withdraws last. The insurance reserve covers
+/// what it can of a slash, by staking it in place of the lost stake
+pub fn sync_delegations<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> StdResult<HandleResponse> {
+    let config = read_config(&deps.storage)?;
+    ensure_not_frozen(&config)?;
+    ensure_not_paused(&deps.storage, "Validator operations", |paused| {
+        paused.validator_ops
+    })?;
+
+    let delegations = get_delegations(&deps.querier, &env.contract.address)?;
+    let mut validator_set = get_validator_set(&deps.storage)?;
+    let mut history = read_slash_history(&deps.storage)?;
+
+    let mut logs: Vec<LogAttribute> = vec![
+        log("action", "sync_delegations"),
+        log("account", env.message.sender.as_str()),
+    ];
+
+    let mut slashes: Vec<(String, u128)> = vec![];
+    let mut total_slashed: u128 = 0;
+    for address in validator_set.addresses() {
+        let on_chain = delegations
+            .iter()
+            .find(|(validator, _)| validator == &address)
+            .map(|(_, amount)| *amount)
+            .unwrap_or_default();
+
+        let recorded = validator_set.set_staked(&address, on_chain, env.block.time)?;
+
+        if recorded > on_chain + SLASH_TOLERANCE {
+            let slashed = recorded - on_chain;
+            debug_print(format!(
+                "validator {} was slashed - expected {}, found {}",
+                address, recorded, on_chain
+            ));
+            history.record(&address, slashed, env.block.time, env.block.height);
+            record_slash(&mut deps.storage, &address, slashed, env.block.time)?;
+            total_slashed += slashed;
+            slashes.push((address.clone(), slashed));
+
+            logs.push(log("slashed", &address));
+            logs.push(log("slash_amount", slashed));
+        } else if on_chain > recorded {
+            logs.push(log("corrected", &address));
+            logs.push(log("surplus", on_chain - recorded));
+        } else if recorded > on_chain {
+            logs.push(log("corrected", &address));
+            logs.push(log("rounding", recorded - on_chain));
+        }
+    }
+
+    let mut messages = vec![];
+    if total_slashed > 0 {
+        set_slash_history(&mut deps.storage, &history)?;
+
+        // the insurance reserve makes up for the slash before it reaches the exchange rate
+        let mut insurance = read_insurance_fund(&deps.storage)?;
+        let covered: u128 = slashes
+            .iter()
+            .map(|(address, slashed)| {
+                insurance.cover(address, *slashed, env.block.time, env.block.height)
+            })
+            .sum();
+
+        if covered > 0 {
+            set_insurance_fund(&mut deps.storage, &insurance)?;
+
+            let policy = read_validator_policy(&deps.storage)?;
+            let strategy = strategy_from_policy(&policy)?;
+            for (validator, amount) in
+                validator_set.delegate(strategy.as_ref(), covered, policy.max_validator_share)?
+            {
+                messages.push(stake_msg(&validator, amount));
+            }
+
+            logs.push(log("insurance_covered", covered));
+        }
+    }
+    validator_set.rebalance();
+    checkpoint_performance(&mut deps.storage, &validator_set, env.block.time)?;
+    set_validator_set(&mut deps.storage, &validator_set)?;
+
+    logs.push(log("total_slashed", total_slashed));
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+/// Runs the next step of a validator set replacement: redelegates from the retiring validators
+/// to the new set, and drops the retiring validators that are empty. Anyone can continue a
+/// migration the admin started
+pub fn continue_validator_migration<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> StdResult<HandleResponse> {
+    let config = read_config(&deps.storage)?;
+    ensure_not_frozen(&config)?;
+    ensure_not_paused(&deps.storage, "Validator operations", |paused| {
+        paused.validator_ops
+    })?;
+
+    let mut validator_set = get_validator_set(&deps.storage)?;
+    if validator_set.retiring().is_empty() {
+        return Err(StdError::generic_err(
+            "There is no validator set migration in progress",
+        ));
+    }
+
+    let max_share = read_validator_policy(&deps.storage)?.max_validator_share;
+    let (messages, mut logs) =
+        migration_step(&mut validator_set, &env, config.unbonding_time, max_share)?;
+
+    checkpoint_performance(&mut deps.storage, &validator_set, env.block.time)?;
+    set_validator_set(&mut deps.storage, &validator_set)?;
+
+    logs.insert(0, log("action", "continue_validator_migration"));
+    logs.insert(1, log("account", env.message.sender.as_str()));
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+/// Redelegates at most MAX_REDELEGATIONS_PER_TX chunks away from the retiring validators. The
+/// logs tell whether the migration is done, or when it can continue
+pub fn migration_step(
+    validator_set: &mut ValidatorSet,
+    env: &Env,
+    unbonding_time: u64,
+    max_share: u64,
+) -> StdResult<(Vec<CosmosMsg>, Vec<LogAttribute>)> {
+    let moves = validator_set.plan_migration(MAX_REDELEGATIONS_PER_TX, max_share, env.block.time);
+    validator_set.apply_moves(&moves, env.block.time + unbonding_time)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut logs: Vec<LogAttribute> = vec![log("moves", moves.len())];
+
+    for planned in moves {
+        messages.push(redelegate_msg(
+            &planned.from,
+            &planned.to,
+            planned.amount.u128(),
+        ));
+        logs.push(log(
+            "redelegate",
+            format!("{} -> {}: {}", planned.from, planned.to, planned.amount),
+        ));
+    }
+
+    for address in validator_set.remove_retired() {
+        logs.push(log("retired", address));
+    }
+
+    let retiring = validator_set.retiring();
+    if retiring.is_empty() {
+        logs.push(log("migration", "done"));
+    } else {
+        logs.push(log("migration", "in progress"));
+        if let Some(next) = retiring
+            .iter()
+            .map(|v| v.redelegation_cooldown)
+            .filter(|cooldown| *cooldown > env.block.time)
+            .min()
+        {
+            logs.push(log("blocked_until", next));
+        }
+    }
+
+    Ok((messages, logs))
+}
+
+pub fn ensure_not_frozen(config: &Config) -> StdResult<()> {
+    if KillSwitch::try_from(config.kill_switch)? != KillSwitch::Closed {
+        return Err(StdError::generic_err(
+            "Contract has been frozen. Validator set can no longer be changed",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Moves `amount` of stake, that was already taken off `source` in our bookkeeping, to the rest
+/// of the set. Redelegates when the chain allows it. Otherwise we undelegate, and queue the
+/// amount to be restaked once it finishes unbonding. Fails if the chain would refuse both. The
+/// stake is spread like any other - preferences for `source` don't carry over
+pub fn redistribute_stake<S: Storage>(
+    storage: &mut S,
+    validator_set: &mut ValidatorSet,
+    source: &Validator,
+    amount: u128,
+    current_time: u64,
+    unbonding_time: u64,
+) -> StdResult<Vec<CosmosMsg>> {
+    if amount == 0 {
+        return Ok(vec![]);
+    }
+
+    if source.is_redelegation_blocked(current_time) {
+        if !source.can_undelegate(current_time) {
+            return Err(StdError::generic_err(format!(
+                "Can't move the stake of {} - redelegation is blocked until {} and it already has {} undelegations in flight",
+                &source.address, source.redelegation_cooldown, MAX_UNBONDING_ENTRIES
+            )));
+        }
+
+        debug_print(format!(
+            "redelegation from {} is blocked until {} - undelegating {} instead",
+            &source.address, source.redelegation_cooldown, amount
+        ));
+        let mut pending = read_pending_restake(storage)?;
+        pending.append(amount, current_time + unbonding_time);
+        set_pending_restake(storage, &pending)?;
+        validator_set.record_unbonding(&source.address, amount, current_time, unbonding_time);
+
+        return Ok(vec![undelegate_msg(&source.address, amount)]);
+    }
+
+    let policy = read_validator_policy(storage)?;
+    let strategy = strategy_from_policy(&policy)?;
+
+    // the stake may end up split between several validators to stay within the concentration cap
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for (dest_validator, to_move) in
+        validator_set.delegate(strategy.as_ref(), amount, policy.max_validator_share)?
+    {
+        validator_set.mark_redelegation_target(&dest_validator, current_time + unbonding_time)?;
+        messages.push(redelegate_msg(&source.address, &dest_validator, to_move));
+    }
+
+    Ok(messages)
+}
@@ -0,0 +1,641 @@
+use std::convert::TryFrom;
+
+use cosmwasm_std::{
+    debug_print, log, Api, CosmosMsg, Env, Extern, HandleResponse, HumanAddr, LogAttribute,
+    Querier, StdError, StdResult, Storage, Uint128,
+};
+
+use crate::constants::MAX_UNBONDS_PER_TX;
+use crate::staking::{exchange_rate, get_backing, get_balance, undelegate_msg};
+use crate::state::{get_address, get_frozen_exchange_rate, store_frozen_exchange_rate};
+use crate::tokens::query_total_supply;
+use crate::types::claims_ledger::{read_claims_ledger, set_claims_ledger, ClaimsLedger};
+use crate::types::config::{read_config, set_config, Config};
+use crate::types::killswitch::KillSwitch;
+use crate::types::pending_restake::read_pending_restake;
+use crate::types::validator_performance::checkpoint_performance;
+use crate::types::validator_set::{get_validator_set, set_validator_set};
+use crate::types::wind_down::{read_wind_down, set_wind_down, WindDown};
+use crate::types::window_manager::{get_window_manager, set_window_manager};
+use crate::types::withdraw_window::set_claim_time;
+use crate::withdraw::calc_withdraw;
+
+/// Freezes the contract and takes the snapshot: the exchange rate, the token supply, what's
+/// delegated and what's owed to queued withdraws. The open window is closed, so its withdraws
+/// are paid like any other queued window. Then the first batch is unbonded
+pub fn start_wind_down<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    mut config: Config,
+) -> StdResult<HandleResponse> {
+    if KillSwitch::try_from(config.kill_switch)? != KillSwitch::Closed {
+        return Err(StdError::generic_err("The wind-down has already started"));
+    }
+
+    let frozen_exchange_rate = exchange_rate(&deps.storage, &deps.querier, 0)?;
+    debug_print(format!("Frozen exchange rate at: {}", frozen_exchange_rate));
+    store_frozen_exchange_rate(&mut deps.storage, &frozen_exchange_rate);
+
+    let supply = query_total_supply(
+        &deps.querier,
+        &config.token_contract,
+        &config.token_contract_hash,
+    )?;
+
+    // taken while the open window still counts against the net assets
+    let pool = get_backing(&deps.querier, &deps.storage, &env.contract.address, 0)?.net_assets();
+
+    let claims = close_open_window(&mut deps.storage, env.block.time, config.unbonding_time)?;
+
+    let validator_set = get_validator_set(&deps.storage)?;
+    let mut wind_down = WindDown {
+        started_at: env.block.time,
+        height: env.block.height,
+        supply,
+        pool: Uint128(pool),
+        delegated: Uint128(validator_set.total_staked()),
+        claims: Uint128(claims.total_outstanding()),
+        unbonded: Uint128::zero(),
+        arrivals: vec![],
+        paid_out: Uint128::zero(),
+        tokens_redeemed: Uint128::zero(),
+    };
+    for entry in read_pending_restake(&deps.storage)?.entries {
+        wind_down.expect(None, entry.amount.u128(), entry.available_time);
+    }
+
+    config.kill_switch = KillSwitch::Unbonding.into();
+    set_config(&mut deps.storage, &config);
+
+    let (messages, mut logs) = unbond_next_batch(deps, &env, &config, &mut wind_down)?;
+    set_wind_down(&mut deps.storage, &wind_down)?;
+
+    logs.insert(0, log("action", "kill_switch_unbond"));
+    logs.push(log("supply", supply));
+    logs.push(log("pool", pool));
+    logs.push(log("claims", wind_down.claims));
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+/// Contracts that pulled the kill switch before the wind-down was snapshotted unbonded all of their
+/// stake at once and froze the exchange rate. The snapshot is taken when they're migrated: if
+/// withdraws are still closed, the pool is what the supply is worth at the frozen rate, and
+/// withdraws open once a full unbonding period has passed. If they're open already, the pool is
+/// what's left in the contract after the queued withdraws
+pub fn snapshot_legacy_wind_down<S: Storage, Q: Querier>(
+    storage: &mut S,
+    querier: &Q,
+    env: &Env,
+) -> StdResult<Option<WindDown>> {
+    let config = read_config(storage)?;
+    let kill_switch = KillSwitch::try_from(config.kill_switch)?;
+    if kill_switch == KillSwitch::Closed || read_wind_down(storage)?.is_some() {
+        return Ok(None);
+    }
+
+    let supply = query_total_supply(querier, &config.token_contract, &config.token_contract_hash)?;
+    let claims = close_open_window(storage, env.block.time, config.unbonding_time)?;
+    let available = get_balance(querier, &get_address(storage)?)?
+        .u128()
+        .saturating_sub(claims.total_outstanding());
+
+    let mut wind_down = WindDown {
+        started_at: env.block.time,
+        height: env.block.height,
+        supply,
+        pool: Uint128(available),
+        delegated: Uint128::zero(),
+        claims: Uint128(claims.total_outstanding()),
+        unbonded: Uint128::zero(),
+        arrivals: vec![],
+        paid_out: Uint128::zero(),
+        tokens_redeemed: Uint128::zero(),
+    };
+
+    if kill_switch == KillSwitch::Unbonding {
+        let pool = calc_withdraw(supply, get_frozen_exchange_rate(storage)?)?;
+        wind_down.pool = Uint128(pool);
+        wind_down.expect(
+            None,
+            pool.saturating_sub(available),
+            env.block.time + config.unbonding_time,
+        );
+    }
+
+    set_wind_down(storage, &wind_down)?;
+
+    Ok(Some(wind_down))
+}
+
+/// Closes the open window, so its withdraws are queued like any other closed window, and returns
+/// the claims ledger
+fn close_open_window<S: Storage>(
+    storage: &mut S,
+    current_time: u64,
+    unbonding_time: u64,
+) -> StdResult<ClaimsLedger> {
+    let mut window_manager = get_window_manager(storage)?;
+    let window = window_manager.current_active_window;
+    let open_window = window_manager.advance_window(current_time)?.amount.u128();
+    set_window_manager(storage, &window_manager)?;
+
+    let mut claims = read_claims_ledger(storage)?;
+    if open_window > 0 {
+        let claim_time = current_time + unbonding_time;
+        set_claim_time(storage, window, claim_time)?;
+        claims.close_window(window, open_window, claim_time);
+        set_claims_ledger(storage, &claims)?;
+    }
+
+    Ok(claims)
+}
+
+/// Unbonds the next batch of validators. Once nothing is left delegated and every expected
+/// undelegation had time to arrive, withdraws are opened. Anyone can call this
+pub fn continue_wind_down<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> StdResult<HandleResponse> {
+    let mut config = read_config(&deps.storage)?;
+    if KillSwitch::try_from(config.kill_switch)? != KillSwitch::Unbonding {
+        return Err(StdError::generic_err("There is no wind-down in progress"));
+    }
+
+    let mut wind_down = read_wind_down(&deps.storage)?
+        .ok_or_else(|| StdError::generic_err("There is no wind-down in progress"))?;
+
+    let (messages, mut logs) = unbond_next_batch(deps, &env, &config, &mut wind_down)?;
+    set_wind_down(&mut deps.storage, &wind_down)?;
+
+    if can_open_withdraws(&deps.storage, &wind_down, env.block.time)? {
+        config.kill_switch = KillSwitch::Open.into();
+        set_config(&mut deps.storage, &config);
+        logs.push(log("withdraws_open", true));
+    } else {
+        let remaining = get_validator_set(&deps.storage)?.total_staked();
+        logs.push(log("remaining", remaining));
+        logs.push(log("last_arrival", wind_down.last_arrival()));
+    }
+
+    logs.insert(0, log("action", "continue_wind_down"));
+    logs.insert(1, log("account", env.message.sender.as_str()));
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+/// Opens withdraws without waiting for the next continue_wind_down. Only once the wind-down is
+/// done - the payouts are priced off the snapshot, so everything it expected has to be back
+pub fn open_withdraws<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    mut config: Config,
+) -> StdResult<HandleResponse> {
+    if KillSwitch::try_from(config.kill_switch)? != KillSwitch::Unbonding {
+        return Err(StdError::generic_err("There is no wind-down in progress"));
+    }
+
+    let wind_down = read_wind_down(&deps.storage)?
+        .ok_or_else(|| StdError::generic_err("There is no wind-down in progress"))?;
+    if !can_open_withdraws(&deps.storage, &wind_down, env.block.time)? {
+        return Err(StdError::generic_err(format!(
+            "Withdraws can only open once nothing is delegated and every undelegation arrived (at {})",
+            wind_down.last_arrival()
+        )));
+    }
+
+    config.kill_switch = KillSwitch::Open.into();
+    set_config(&mut deps.storage, &config);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "kill_switch_open_withdraws")],
+        data: None,
+    })
+}
+
+/// nothing is left delegated, and every expected undelegation had time to arrive
+fn can_open_withdraws<S: Storage>(
+    storage: &S,
+    wind_down: &WindDown,
+    current_time: u64,
+) -> StdResult<bool> {
+    Ok(get_validator_set(storage)?.total_staked() == 0 && wind_down.last_arrival() <= current_time)
+}
+
+fn unbond_next_batch<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    config: &Config,
+    wind_down: &mut WindDown,
+) -> StdResult<(Vec<CosmosMsg>, Vec<LogAttribute>)> {
+    let mut validator_set = get_validator_set(&deps.storage)?;
+
+    let batch = validator_set.unbond_batch(
+        MAX_UNBONDS_PER_TX as usize,
+        env.block.time,
+        config.unbonding_time,
+    );
+
+    let mut messages = vec![];
+    let mut unbonded = 0u128;
+    for (validator, amount) in batch.iter() {
+        messages.push(undelegate_msg(validator, *amount));
+        wind_down.expect(
+            Some(validator.clone()),
+            *amount,
+            env.block.time + config.unbonding_time,
+        );
+        unbonded += amount;
+    }
+    wind_down.unbonded = Uint128(wind_down.unbonded.u128() + unbonded);
+
+    checkpoint_performance(&mut deps.storage, &validator_set, env.block.time)?;
+    set_validator_set(&mut deps.storage, &validator_set)?;
+
+    Ok((
+        messages,
+        vec![log("validators", batch.len()), log("unbonded", unbonded)],
+    ))
+}
+
+/// What `tokens` are worth once withdraws are open: a pro-rata share of the pool frozen at the
+/// snapshot, so redeeming early or late doesn't matter and SCRT sent to the contract since doesn't
+/// move it. If less arrived than expected (a slash while unbonding), the tokens that are left
+/// share the shortfall
+pub fn wind_down_payout<S: Storage, Q: Querier>(
+    storage: &S,
+    querier: &Q,
+    contract_address: &HumanAddr,
+    tokens: Uint128,
+) -> StdResult<u128> {
+    let wind_down = read_wind_down(storage)?
+        .ok_or_else(|| StdError::generic_err("There is no wind-down in progress"))?;
+
+    let (pool, unredeemed) = remaining_pool(storage, querier, contract_address, &wind_down)?;
+    if unredeemed == 0 {
+        return Ok(0);
+    }
+
+    Ok(tokens.multiply_ratio(pool, unredeemed).u128())
+}
+
+/// What's left of the pool, capped by what's in the contract after the queued withdraws, and the
+/// tokens of the snapshot that weren't redeemed yet
+pub fn remaining_pool<S: Storage, Q: Querier>(
+    storage: &S,
+    querier: &Q,
+    contract_address: &HumanAddr,
+    wind_down: &WindDown,
+) -> StdResult<(u128, u128)> {
+    let available = get_balance(querier, contract_address)?
+        .u128()
+        .saturating_sub(read_claims_ledger(storage)?.total_outstanding());
+    let pool = std::cmp::min(
+        wind_down
+            .pool
+            .u128()
+            .saturating_sub(wind_down.paid_out.u128()),
+        available,
+    );
+
+    Ok((
+        pool,
+        wind_down
+            .supply
+            .u128()
+            .saturating_sub(wind_down.tokens_redeemed.u128()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_env, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
+    use std::collections::VecDeque;
+
+    use crate::constants::MAX_UNBONDING_ENTRIES;
+    use crate::mock_querier::ChainQuerier;
+    use crate::state::store_address;
+    use crate::types::activation_fee::set_activation_fee;
+    use crate::types::claims_ledger::ClaimsLedger;
+    use crate::types::pending_restake::{set_pending_restake, PendingRestake};
+    use crate::types::validator_set::{UnbondingEntry, Validator, ValidatorSet};
+    use crate::types::wind_down::ExpectedArrival;
+    use crate::types::window_manager::WindowManager;
+
+    const UNBONDING_TIME: u64 = 1_814_400;
+
+    fn validator(address: &str, staked: u128) -> Validator {
+        Validator {
+            address: address.to_string(),
+            staked,
+            weight: 10,
+            status: 0,
+            redelegation_cooldown: 0,
+            preferred: 0,
+            unbonding: vec![],
+            last_sync: 0,
+        }
+    }
+
+    fn store_state<S: Storage>(storage: &mut S, validators: Vec<Validator>) {
+        store_address(storage, &HumanAddr(MOCK_CONTRACT_ADDR.to_string()));
+        set_config(
+            storage,
+            &Config {
+                admin: HumanAddr("admin".to_string()),
+                token_contract: HumanAddr("token".to_string()),
+                token_contract_hash: "hash".to_string(),
+                gov_token: HumanAddr("gov".to_string()),
+                gov_token_hash: "hash".to_string(),
+                voting_admin: HumanAddr("admin".to_string()),
+                unbonding_time: UNBONDING_TIME,
+                kill_switch: 0,
+                dev_address: HumanAddr("dev".to_string()),
+                dev_fee: 1000,
+                shared_withdrawals: 0,
+            },
+        );
+        set_activation_fee(storage, &0).unwrap();
+
+        let mut window_manager = WindowManager::default();
+        window_manager.window.coins.denom = "uscrt".to_string();
+        set_window_manager(storage, &window_manager).unwrap();
+
+        set_validator_set(
+            storage,
+            &ValidatorSet::from_parts(VecDeque::from(validators), 0),
+        )
+        .unwrap();
+    }
+
+    /// 1_000 tokens sharing a pool of 2_000, next to 500 owed to queued withdraws
+    fn store_open_wind_down<S: Storage>(storage: &mut S) {
+        store_state(storage, vec![]);
+
+        let mut claims = ClaimsLedger::default();
+        claims.close_window(0, 500, 1_000);
+        set_claims_ledger(storage, &claims).unwrap();
+
+        set_wind_down(
+            storage,
+            &WindDown {
+                started_at: 0,
+                height: 0,
+                supply: Uint128(1_000),
+                pool: Uint128(2_000),
+                delegated: Uint128(2_000),
+                claims: Uint128(500),
+                unbonded: Uint128(2_000),
+                arrivals: vec![],
+                paid_out: Uint128::zero(),
+                tokens_redeemed: Uint128::zero(),
+            },
+        )
+        .unwrap();
+    }
+
+    fn chain(balance: u128) -> ChainQuerier {
+        ChainQuerier {
+            balance,
+            delegations: vec![],
+            supply: 1_000,
+        }
+    }
+
+    /// pays out `tokens` like release_tokens would, and returns the SCRT
+    fn redeem<S: Storage>(storage: &mut S, balance: &mut u128, tokens: u128) -> u128 {
+        let contract = HumanAddr(MOCK_CONTRACT_ADDR.to_string());
+        let scrt = wind_down_payout(storage, &chain(*balance), &contract, Uint128(tokens)).unwrap();
+
+        let mut wind_down = read_wind_down(storage).unwrap().unwrap();
+        wind_down.record_payout(tokens, scrt);
+        set_wind_down(storage, &wind_down).unwrap();
+        *balance -= scrt;
+
+        scrt
+    }
+
+    #[test]
+    fn test_payout_is_pro_rata() {
+        let mut storage = MockStorage::new();
+        store_open_wind_down(&mut storage);
+        let contract = HumanAddr(MOCK_CONTRACT_ADDR.to_string());
+
+        assert_eq!(
+            wind_down_payout(&storage, &chain(2_500), &contract, Uint128(300)).unwrap(),
+            600
+        );
+
+        // SCRT sent to the contract later doesn't move the payout
+        assert_eq!(
+            wind_down_payout(&storage, &chain(10_000), &contract, Uint128(300)).unwrap(),
+            600
+        );
+
+        // if less arrived, everyone shares the shortfall - the queued withdraws are paid first
+        assert_eq!(
+            wind_down_payout(&storage, &chain(2_000), &contract, Uint128(300)).unwrap(),
+            450
+        );
+    }
+
+    #[test]
+    fn test_remaining_pool() {
+        let mut storage = MockStorage::new();
+        store_open_wind_down(&mut storage);
+        let contract = HumanAddr(MOCK_CONTRACT_ADDR.to_string());
+
+        let mut wind_down = read_wind_down(&storage).unwrap().unwrap();
+        assert_eq!(
+            remaining_pool(&storage, &chain(2_500), &contract, &wind_down).unwrap(),
+            (2_000, 1_000)
+        );
+
+        wind_down.record_payout(300, 600);
+        assert_eq!(
+            remaining_pool(&storage, &chain(1_900), &contract, &wind_down).unwrap(),
+            (1_400, 700)
+        );
+        assert_eq!(
+            remaining_pool(&storage, &chain(10_000), &contract, &wind_down).unwrap(),
+            (1_400, 700)
+        );
+        assert_eq!(
+            remaining_pool(&storage, &chain(1_200), &contract, &wind_down).unwrap(),
+            (700, 700)
+        );
+    }
+
+    #[test]
+    fn test_redeem_order_does_not_matter() {
+        let mut storage = MockStorage::new();
+        store_open_wind_down(&mut storage);
+        let mut balance = 2_500;
+        let alice = redeem(&mut storage, &mut balance, 300);
+        let bob = redeem(&mut storage, &mut balance, 700);
+        assert_eq!((alice, bob), (600, 1_400));
+        assert_eq!(balance, 500);
+
+        let mut storage = MockStorage::new();
+        store_open_wind_down(&mut storage);
+        let mut balance = 2_500;
+        let bob = redeem(&mut storage, &mut balance, 700);
+        let alice = redeem(&mut storage, &mut balance, 300);
+        assert_eq!((alice, bob), (600, 1_400));
+        assert_eq!(balance, 500);
+    }
+
+    #[test]
+    fn test_wind_down_unbonds_in_batches() {
+        let mut deps = Extern {
+            storage: MockStorage::new(),
+            api: MockApi::new(20),
+            querier: chain(0),
+        };
+        let mut env = mock_env("admin", &[]);
+        let start = env.block.time;
+
+        // `full` can't take another undelegation until its oldest one finishes
+        let mut full = validator("full", 100_000);
+        for i in 0..MAX_UNBONDING_ENTRIES as u64 {
+            full.unbonding.push(UnbondingEntry {
+                amount: Uint128(1),
+                completion_time: start + 100 + i,
+            });
+        }
+        let mut validators = vec![full];
+        for i in 0..MAX_UNBONDS_PER_TX {
+            validators.push(validator(&format!("v{}", i), 100_000));
+        }
+        store_state(&mut deps.storage, validators);
+
+        let config = read_config(&deps.storage).unwrap();
+        let response = start_wind_down(&mut deps, env.clone(), config).unwrap();
+        assert_eq!(response.messages.len(), MAX_UNBONDS_PER_TX as usize);
+
+        let wind_down = read_wind_down(&deps.storage).unwrap().unwrap();
+        assert_eq!(wind_down.supply, Uint128(1_000));
+        assert_eq!(wind_down.pool, Uint128(1_100_000));
+        assert_eq!(wind_down.delegated, Uint128(1_100_000));
+        assert_eq!(wind_down.unbonded, Uint128(1_000_000));
+        assert!(wind_down
+            .arrivals
+            .iter()
+            .all(|a| a.validator != Some("full".to_string())));
+
+        // `full` is still waiting
+        let response = continue_wind_down(&mut deps, env.clone()).unwrap();
+        assert!(response.messages.is_empty());
+        assert_eq!(
+            get_validator_set(&deps.storage).unwrap().total_staked(),
+            100_000
+        );
+
+        env.block.time = start + 100;
+        let response = continue_wind_down(&mut deps, env).unwrap();
+        assert_eq!(response.messages.len(), 1);
+        assert_eq!(get_validator_set(&deps.storage).unwrap().total_staked(), 0);
+
+        let wind_down = read_wind_down(&deps.storage).unwrap().unwrap();
+        assert_eq!(wind_down.unbonded, Uint128(1_100_000));
+        assert_eq!(wind_down.last_arrival(), start + 100 + UNBONDING_TIME);
+    }
+
+    #[test]
+    fn test_withdraws_open_once_everything_arrived() {
+        let mut deps = Extern {
+            storage: MockStorage::new(),
+            api: MockApi::new(20),
+            querier: chain(0),
+        };
+        let mut env = mock_env("admin", &[]);
+        let start = env.block.time;
+        store_state(&mut deps.storage, vec![validator("a", 100_000)]);
+
+        // a queued window's stake that's on its way to be restaked, and arrives after the rest
+        let restake_time = start + UNBONDING_TIME + 1_000;
+        let mut restake = PendingRestake::default();
+        restake.append(50_000, restake_time);
+        set_pending_restake(&mut deps.storage, &restake).unwrap();
+
+        let config = read_config(&deps.storage).unwrap();
+        start_wind_down(&mut deps, env.clone(), config).unwrap();
+
+        let wind_down = read_wind_down(&deps.storage).unwrap().unwrap();
+        assert_eq!(wind_down.pool, Uint128(150_000));
+        assert!(wind_down.arrivals.contains(&ExpectedArrival {
+            validator: None,
+            amount: Uint128(50_000),
+            available_time: restake_time,
+        }));
+        assert_eq!(wind_down.last_arrival(), restake_time);
+
+        // nothing is delegated, but nothing arrived yet
+        let config = read_config(&deps.storage).unwrap();
+        assert!(open_withdraws(&mut deps, env.clone(), config).is_err());
+
+        // the undelegation arrived, the restake didn't
+        env.block.time = start + UNBONDING_TIME;
+        continue_wind_down(&mut deps, env.clone()).unwrap();
+        let config = read_config(&deps.storage).unwrap();
+        assert_eq!(
+            KillSwitch::try_from(config.kill_switch).unwrap(),
+            KillSwitch::Unbonding
+        );
+        assert!(open_withdraws(&mut deps, env.clone(), config).is_err());
+
+        env.block.time = restake_time;
+        let config = read_config(&deps.storage).unwrap();
+        open_withdraws(&mut deps, env.clone(), config).unwrap();
+        assert_eq!(
+            KillSwitch::try_from(read_config(&deps.storage).unwrap().kill_switch).unwrap(),
+            KillSwitch::Open
+        );
+
+        assert!(continue_wind_down(&mut deps, env).is_err());
+    }
+
+    #[test]
+    fn test_withdraws_stay_closed_while_stake_is_delegated() {
+        let mut deps = Extern {
+            storage: MockStorage::new(),
+            api: MockApi::new(20),
+            querier: chain(0),
+        };
+        let env = mock_env("admin", &[]);
+        store_state(&mut deps.storage, vec![validator("a", 100_000)]);
+
+        set_wind_down(
+            &mut deps.storage,
+            &WindDown {
+                started_at: env.block.time,
+                height: env.block.height,
+                supply: Uint128(1_000),
+                pool: Uint128(100_000),
+                delegated: Uint128(100_000),
+                claims: Uint128::zero(),
+                unbonded: Uint128::zero(),
+                arrivals: vec![],
+                paid_out: Uint128::zero(),
+                tokens_redeemed: Uint128::zero(),
+            },
+        )
+        .unwrap();
+        let mut config = read_config(&deps.storage).unwrap();
+        config.kill_switch = KillSwitch::Unbonding.into();
+        set_config(&mut deps.storage, &config);
+
+        assert!(open_withdraws(&mut deps, env, config).is_err());
+    }
+}
@@ -2,6 +2,7 @@ use crate::types::activation_fee::{read_activation_fee, set_activation_fee};
 use crate::types::config::read_config;
 use crate::types::window_manager::{get_window_manager, set_window_manager};
 use crate::types::withdraw_window::set_claim_time;
+use crate::validators::ensure_not_frozen;
 use crate::withdraw::{check_window_advance, perform_window_unbond};
 use cosmwasm_std::{
     log, Api, BankMsg, Coin, CosmosMsg, Env, Extern, HandleResponse, Querier, StdError, StdResult,
@@ -15,6 +16,7 @@ pub fn advance_window<S: Storage, A: Api, Q: Querier>(
 ) -> StdResult<HandleResponse> {
     let mut messages = vec![];
     let constants = read_config(&deps.storage)?;
+    ensure_not_frozen(&constants)?;
     let mut window_manager = get_window_manager(&deps.storage)?;
     let fee_for_activation;
     if check_window_advance(&env, &window_manager) {
@@ -1,4 +1,3 @@
-use std::cmp::min;
 use std::convert::TryFrom;
 
 use cosmwasm_std::{
@@ -10,16 +9,24 @@ use rust_decimal::Decimal;
 use secret_toolkit::snip20;
 
 use crate::msg::WithdrawRequest;
-use crate::staking::{exchange_rate, get_balance, undelegate_msg};
-use crate::state::get_frozen_exchange_rate;
+use crate::pause::ensure_not_paused;
+use crate::rate_guard::{ensure_exchange_rate, start_window_reference};
+use crate::rate_oracle::record_exchange_rate;
+use crate::staking::{exchange_rate, sent_scrt, undelegate_msg};
+use crate::types::claims_ledger::{read_claims_ledger, set_claims_ledger};
 use crate::types::config::read_config;
+use crate::types::delegation_strategy::strategy_from_policy;
 use crate::types::killswitch::KillSwitch;
 use crate::types::user_withdraws::UserWithdrawManager;
+use crate::types::validator_performance::checkpoint_performance;
+use crate::types::validator_policy::read_validator_policy;
 use crate::types::validator_set::{get_validator_set, set_validator_set};
+use crate::types::wind_down::{read_wind_down, set_wind_down};
 use crate::types::window_manager::{get_window_manager, set_window_manager, WindowManager};
-use crate::types::withdraw_window::set_claim_time;
+use crate::types::withdraw_window::{get_claim_time, set_claim_time};
+use crate::types::yield_ledger::{read_yield_ledger, record_flows, set_yield_ledger};
 use crate::utils::perform_helper_claims;
-use std::ops::Div;
+use crate::wind_down::wind_down_payout;
 
 const MINIMUM_WITHDRAW: u128 = 1_000_000; // 1 scrt
 
@@ -41,11 +48,13 @@ pub fn try_withdraw<S: Storage, A: Api, Q: Querier>(
         ));
     }
 
+    ensure_not_paused(&deps.storage, "Withdraws", |paused| paused.withdraws)?;
+
     let kill_switch = KillSwitch::try_from(constants.kill_switch)?;
 
     if kill_switch == KillSwitch::Unbonding {
         return Err(StdError::generic_err(
-                "Contract has been frozen. You must wait till unbonding has finished, then you will be able to withdraw your funds",
+                "Contract has been frozen. You must wait till unbonding has finished (see continue_wind_down), then you will be able to withdraw your funds",
             ));
     }
 
@@ -53,7 +62,9 @@ pub fn try_withdraw<S: Storage, A: Api, Q: Querier>(
         return release_tokens(deps, &env, amount, sender);
     }
 
-    let exch_rate = exchange_rate(&deps.storage, &deps.querier)?;
+    ensure_exchange_rate(&mut deps.storage, &deps.querier, &env)?;
+
+    let exch_rate = exchange_rate(&deps.storage, &deps.querier, sent_scrt(&env))?;
 
     // if amount.u128() < EXCHANGE_RATE_RESOLUTION as u128 {
     //     return Err(StdError::generic_err(
@@ -74,8 +85,6 @@ pub fn try_withdraw<S: Storage, A: Api, Q: Querier>(
         )));
     }
 
-    perform_helper_claims(deps, &env, &constants, &mut messages)?;
-
     // let rewards = get_rewards_limited(
     //     &deps.querier,
     //     &env.contract.address,
@@ -117,6 +126,8 @@ pub fn try_withdraw<S: Storage, A: Api, Q: Querier>(
     //     messages.extend((&validator_set).withdraw_rewards_messages(Some(top_5_validators)));
     // }
 
+    record_flows(&mut deps.storage, 0, 0, 0, unbond_amount)?;
+
     let mut window_manager = get_window_manager(&deps.storage)?;
 
     window_manager.withdraw(&mut deps.storage, &sender, Uint128::from(unbond_amount))?;
@@ -166,6 +177,9 @@ pub fn try_withdraw<S: Storage, A: Api, Q: Querier>(
         perform_window_unbond(deps, &env, &mut window_manager, &mut messages)?;
     }
 
+    // after the window snapshot - claims leave the claims ledger before the SCRT leaves our balance
+    perform_helper_claims(deps, &env, &constants, &mut messages)?;
+
     set_window_manager(&mut deps.storage, &window_manager)?;
 
     Ok(HandleResponse {
@@ -182,30 +196,24 @@ pub fn try_withdraw<S: Storage, A: Api, Q: Querier>(
 fn unbond<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     messages: &mut Vec<CosmosMsg>,
-    mut unbond_amount: u128,
+    unbond_amount: u128,
+    current_time: u64,
 ) -> StdResult<()> {
     let mut validator_set = get_validator_set(&deps.storage)?;
+    let strategy = strategy_from_policy(&read_validator_policy(&deps.storage)?)?;
+    let unbonding_time = read_config(&deps.storage)?.unbonding_time;
 
-    while unbond_amount > 0 {
-        if let Some(validator) = validator_set.clone().next_to_unbond() {
-            if validator.staked == 0 {
-                // we can't unbond any more, no validator has any stake left!
-                break;
-            }
-
-            // don't unbond more than 50% of the a validator's stake and then re-evaluate
-            let to_unbond = min(validator.staked.div(2 as u128) as u128, unbond_amount);
-            validator_set.unbond(to_unbond)?;
-            validator_set.rebalance();
-            messages.push(undelegate_msg(&validator.address, to_unbond));
-            debug_print(format!(
-                "\x1B[34m ********* undelegating {} from {} ****** \x1B[0m",
-                to_unbond, &validator.address
-            ));
-            unbond_amount = unbond_amount.saturating_sub(to_unbond);
-        }
+    // we might have to unbond from multiple validators
+    for (validator, to_unbond) in validator_set.undelegate(strategy.as_ref(), unbond_amount)? {
+        messages.push(undelegate_msg(&validator, to_unbond));
+        validator_set.record_unbonding(&validator, to_unbond, current_time, unbonding_time);
+        debug_print(format!(
+            "\x1B[34m ********* undelegating {} from {} ****** \x1B[0m",
+            to_unbond, &validator
+        ));
     }
 
+    checkpoint_performance(&mut deps.storage, &validator_set, current_time)?;
     set_validator_set(&mut deps.storage, &validator_set)
 }
 
@@ -243,16 +251,19 @@ fn release_tokens<S: Storage, A: Api, Q: Querier>(
     let constants = read_config(&deps.storage)?;
 
     debug_print(format!("** tokens withdrawn: {}", amount));
-    let xrate = get_frozen_exchange_rate(&deps.storage)?;
-    debug_print(format!("** Frozen exchange rate: {}", xrate.to_string()));
-    let scrt_amount = calc_withdraw(amount, xrate)?;
+    let scrt_amount =
+        wind_down_payout(&deps.storage, &deps.querier, &env.contract.address, amount)?;
     debug_print(format!("** SCRT amount withdrawn: {}", scrt_amount));
-    let my_balance = get_balance(&deps.querier, &env.contract.address)?;
-    debug_print(format!("** contract balance: {}", my_balance));
+    record_flows(&mut deps.storage, 0, 0, 0, scrt_amount)?;
+
+    if let Some(mut wind_down) = read_wind_down(&deps.storage)? {
+        wind_down.record_payout(amount.u128(), scrt_amount);
+        set_wind_down(&mut deps.storage, &wind_down)?;
+    }
 
     let scrt_coin = Coin {
         denom: "uscrt".to_string(),
-        amount: min(my_balance, Uint128::from(scrt_amount)),
+        amount: Uint128::from(scrt_amount),
     };
 
     messages.push(snip20::burn_msg(
@@ -305,7 +316,37 @@ pub fn perform_window_unbond<S: Storage, A: Api, Q: Querier>(
     window_manager: &mut WindowManager,
     messages: &mut Vec<CosmosMsg>,
 ) -> StdResult<()> {
+    let window = window_manager.current_active_window;
+    snapshot_exchange_rate(deps, env, window)?;
     let withdraw_amount = window_manager.advance_window(env.block.time)?;
 
-    unbond(deps, messages, withdraw_amount.amount.u128())
+    let mut claims = read_claims_ledger(&deps.storage)?;
+    claims.close_window(
+        window,
+        withdraw_amount.amount.u128(),
+        get_claim_time(&deps.storage, window).unwrap_or(env.block.time),
+    );
+    set_claims_ledger(&mut deps.storage, &claims)?;
+
+    unbond(
+        deps,
+        messages,
+        withdraw_amount.amount.u128(),
+        env.block.time,
+    )
+}
+
+/// records the exchange rate as the window closes - see query_apr and the exchange rate history.
+/// Subscribers are only updated by compounds, so none of them can hold up withdraws
+fn snapshot_exchange_rate<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    window: u64,
+) -> StdResult<()> {
+    let point = record_exchange_rate(&mut deps.storage, &deps.querier, env)?;
+    start_window_reference(&mut deps.storage, &point)?;
+
+    let mut ledger = read_yield_ledger(&deps.storage)?;
+    ledger.snapshot(window, env.block.time, point.rate);
+    set_yield_ledger(&mut deps.storage, &ledger)
 }